@@ -0,0 +1,80 @@
+// Read config.toml, the settings of the palette itself
+
+use std::{fs, path::Path};
+
+use crate::models::app_config::AppConfig;
+
+// A missing file means the defaults, a broken one is an error
+pub fn load_app_config<P: AsRef<Path>>(path: P) -> Result<AppConfig, String> {
+    let path = path.as_ref();
+    if !path.exists() {
+        return Ok(AppConfig::default());
+    }
+
+    let content = fs::read_to_string(path).map_err(|e| format!("Could not read {path:?}: {e}"))?;
+    toml::from_str(&content).map_err(|e| format!("Could not parse {path:?}: {e}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::hotkey::{HotkeyModifiers, Key, KeyboardShortcut};
+
+    #[test]
+    fn parses_activation_and_defaults_the_rest() {
+        let config: AppConfig = toml::from_str(
+            r#"
+activation = { mods = ["alt"], key = "Space" }
+extensions = ["extensions/chrome.toml"]
+"#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            KeyboardShortcut::from(&config.activation),
+            KeyboardShortcut {
+                modifier: HotkeyModifiers {
+                    alt: true,
+                    ..Default::default()
+                },
+                key: Key::Space,
+            }
+        );
+        assert_eq!(config.extensions.len(), 1);
+        assert_eq!(config.ui.max_results, 8);
+    }
+
+    #[test]
+    fn extensions_are_paths_or_tables() {
+        let config: AppConfig = toml::from_str(
+            r#"
+extensions = ["chrome.toml", { path = "team/*.toml" }, { path = "zoom.toml", enabled = false }]
+"#,
+        )
+        .unwrap();
+
+        let entries: Vec<(&str, bool)> = config
+            .extensions
+            .iter()
+            .map(|e| (e.path(), e.enabled()))
+            .collect();
+        assert_eq!(
+            entries,
+            vec![
+                ("chrome.toml", true),
+                ("team/*.toml", true),
+                ("zoom.toml", false)
+            ]
+        );
+        assert_eq!(config.search_dirs.len(), 3);
+    }
+
+    #[test]
+    fn empty_file_is_the_default_config() {
+        let config: AppConfig = toml::from_str("").unwrap();
+        assert_eq!(
+            KeyboardShortcut::from(&config.activation).to_string(),
+            "Ctrl+Shift+P"
+        );
+    }
+}
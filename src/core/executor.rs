@@ -0,0 +1,232 @@
+// Runs a UnitAction against the window the palette was opened over
+
+use std::{
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    },
+    thread::{self, JoinHandle},
+    time::Duration,
+};
+
+use log::{error, warn};
+use raw_window_handle::RawWindowHandle;
+
+use crate::{
+    core::{macros, registry::registry::UnitAction},
+    models::action::{ActionKind, MacroStep},
+    platform::{
+        clipboard::Clipboard,
+        key_sender::KeySender,
+        platform_interface::{focus_context, focus_process, get_foreground_context},
+        spawner::Spawner,
+    },
+};
+
+// Time for the target window to become active before keys are injected
+pub const FOCUS_SETTLE: Duration = Duration::from_millis(50);
+
+// Macro running on its own thread
+struct RunningMacro {
+    cancel: Arc<AtomicBool>,
+    thread: JoinHandle<()>,
+}
+
+pub struct ActionExecutor {
+    sender: Arc<Mutex<Box<dyn KeySender + Send>>>,
+    spawner: Arc<Mutex<Box<dyn Spawner + Send>>>,
+    clipboard: Arc<Mutex<Box<dyn Clipboard + Send>>>,
+    target: Option<RawWindowHandle>, // Foreground window when the palette opened
+    running: Option<RunningMacro>,
+}
+
+impl ActionExecutor {
+    pub fn new(
+        sender: Box<dyn KeySender + Send>,
+        spawner: Box<dyn Spawner + Send>,
+        clipboard: Box<dyn Clipboard + Send>,
+    ) -> ActionExecutor {
+        ActionExecutor {
+            sender: Arc::new(Mutex::new(sender)),
+            spawner: Arc::new(Mutex::new(spawner)),
+            clipboard: Arc::new(Mutex::new(clipboard)),
+            target: None,
+            running: None,
+        }
+    }
+
+    // Must be called before the palette takes focus
+    pub fn capture_target(&mut self) {
+        self.target = get_foreground_context();
+    }
+
+    pub fn target(&self) -> Option<&RawWindowHandle> {
+        self.target.as_ref()
+    }
+
+    // The palette has to be hidden already, otherwise the keys go to the palette.
+    // Spawns do not need the target window. A macro still running is cancelled
+    // first. `lookup` finds the actions a macro runs, see MasterRegistry::find_action
+    pub fn execute(
+        &mut self,
+        action: &UnitAction,
+        lookup: &dyn Fn(&str) -> Option<UnitAction>,
+    ) -> Result<(), String> {
+        self.cancel();
+        // Before focus moves, so a broken macro does nothing
+        let steps = match &action.kind {
+            ActionKind::Shortcut(sequence) => vec![MacroStep::Send(sequence.clone())],
+            ActionKind::Macro(steps) => macros::expand(&action.id(), steps, lookup)?,
+            ActionKind::Spawn(spawn) => vec![MacroStep::Spawn(spawn.clone())],
+            ActionKind::TypeText(snippet) => vec![MacroStep::TypeText(snippet.clone())],
+        };
+
+        let needs_target = !matches!(action.kind, ActionKind::Spawn(_));
+        if let Some(target) = self.target.as_ref().filter(|_| needs_target) {
+            if !focus_context(target) {
+                warn!("Could not give focus back to {target:?}");
+            }
+            thread::sleep(FOCUS_SETTLE);
+        }
+
+        // Off the UI thread when it waits, opening the palette cancels it then
+        let waits = match &action.kind {
+            ActionKind::Macro(_) => true,
+            ActionKind::Shortcut(sequence) => sequence.chords.len() > 1 && sequence.delay_ms > 0,
+            ActionKind::Spawn(_) | ActionKind::TypeText(_) => false,
+        };
+        if waits {
+            self.start_macro(action.action_name.clone(), steps);
+            return Ok(());
+        }
+        macros::run_steps(
+            &steps,
+            &self.sender,
+            &self.spawner,
+            &self.clipboard,
+            &focus_process,
+            &AtomicBool::new(false),
+        )
+    }
+
+    // Stops the running macro, if any, and waits for its current step
+    pub fn cancel(&mut self) {
+        if let Some(running) = self.running.take() {
+            running.cancel.store(true, Ordering::Relaxed);
+            let _ = running.thread.join();
+        }
+    }
+
+    fn start_macro(&mut self, name: String, steps: Vec<MacroStep>) {
+        let cancel = Arc::new(AtomicBool::new(false));
+        let (sender, spawner) = (Arc::clone(&self.sender), Arc::clone(&self.spawner));
+        let clipboard = Arc::clone(&self.clipboard);
+        let thread_cancel = Arc::clone(&cancel);
+        let thread = thread::spawn(move || {
+            let result = macros::run_steps(
+                &steps,
+                &sender,
+                &spawner,
+                &clipboard,
+                &focus_process,
+                &thread_cancel,
+            );
+            if let Err(e) = result {
+                error!("Macro {name} stopped: {e}");
+            }
+        });
+        self.running = Some(RunningMacro { cancel, thread });
+    }
+}
+
+impl Drop for ActionExecutor {
+    fn drop(&mut self) {
+        self.cancel();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        models::{
+            action::{ActionKind, FocusState, Priority, Spawn},
+            hotkey::{HotkeyModifiers, Key, KeyboardShortcut, Modifier},
+        },
+        platform::{
+            clipboard::MemoryClipboard,
+            key_sender::{KeyCode, KeyEvent, RecordingKeySender},
+            spawner::RecordingSpawner,
+        },
+    };
+
+    fn action(focus_state: FocusState, kind: ActionKind) -> UnitAction {
+        UnitAction {
+            app_id: "chrome".into(),
+            app_name: "Chrome".into(),
+            action_id: "new_tab".into(),
+            action_name: "New tab".into(),
+            focus_state,
+            shortcut: kind.shortcut().map(ToString::to_string),
+            kind,
+            aliases: vec![],
+            description: None,
+            keywords: vec![],
+            category: None,
+            priority: Priority::Application,
+        }
+    }
+
+    #[test]
+    fn running_an_action_sends_its_shortcut() {
+        let recorder = RecordingKeySender::default();
+        let mut executor = ActionExecutor::new(
+            Box::new(recorder.clone()),
+            Box::new(RecordingSpawner::default()),
+            Box::new(MemoryClipboard::default()),
+        );
+
+        let new_tab = action(
+            FocusState::Focused,
+            ActionKind::Shortcut(
+                KeyboardShortcut {
+                    modifier: HotkeyModifiers {
+                        control: true,
+                        ..Default::default()
+                    },
+                    key: Key::KeyT,
+                }
+                .into(),
+            ),
+        );
+        executor.execute(&new_tab, &|_| None).unwrap();
+
+        assert_eq!(
+            recorder.events(),
+            vec![
+                KeyEvent::Down(KeyCode::Modifier(Modifier::Control)),
+                KeyEvent::Down(KeyCode::Key(Key::KeyT)),
+                KeyEvent::Up(KeyCode::Key(Key::KeyT)),
+                KeyEvent::Up(KeyCode::Modifier(Modifier::Control)),
+            ]
+        );
+    }
+
+    #[test]
+    fn running_a_spawn_action_sends_no_keys() {
+        let recorder = RecordingKeySender::default();
+        let spawns = RecordingSpawner::default();
+        let mut executor = ActionExecutor::new(
+            Box::new(recorder.clone()),
+            Box::new(spawns.clone()),
+            Box::new(MemoryClipboard::default()),
+        );
+
+        let docs = Spawn::Open("https://docs.rs".into());
+        let open_docs = action(FocusState::Global, ActionKind::Spawn(docs.clone()));
+        executor.execute(&open_docs, &|_| None).unwrap();
+
+        assert_eq!(spawns.spawned(), [docs]);
+        assert!(recorder.events().is_empty());
+    }
+}
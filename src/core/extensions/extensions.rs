@@ -1,35 +1,52 @@
 // Read the extensions file and build a application registry
 
-use std::{fs, path::Path};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
 
-use crate::{core::registry::registry::Application, models::config::Config};
+use crate::{
+    core::extensions::migration,
+    models::{config::Config, extension_error::ExtensionError},
+};
 
-pub fn load_config<P: AsRef<Path>>(path: P) -> Result<Config, String> {
-    // 1. Read file. If it fails, convert the io::Error to your String error and return early.
-    let content = fs::read_to_string(path).map_err(|e| format!("Could not read file: {e}"))?;
+// An extension file as written, `source` is the text its spans point into
+#[derive(Debug)]
+pub struct ExtensionFile {
+    pub path: PathBuf,
+    pub source: String,
+    pub config: Config,
+    pub deprecations: Vec<ExtensionError>, // Left by migrating an older schema version
+}
 
-    // 2. Parse TOML. If it fails, convert the toml::de::Error to String and return early.
-    let config: Config =
-        toml::from_str(&content).map_err(|e| format!("Could not parse config: {e}"))?;
+pub fn load_config<P: AsRef<Path>>(path: P) -> Result<ExtensionFile, ExtensionError> {
+    let path = path.as_ref().to_path_buf();
+    let source = fs::read_to_string(&path)
+        .map_err(|e| ExtensionError::file(path.clone(), format!("Could not read file: {e}")))?;
 
-    Ok(config)
-}
+    let (config, deprecations) = migration::parse(&path, &source)?;
 
-fn build_application_registry_from_toml_config(extention_config: Config) -> Application {
-    todo!("build mapping");
+    Ok(ExtensionFile {
+        path,
+        source,
+        config,
+        deprecations,
+    })
 }
 
 #[test]
 fn deserializes_inline_toml() {
+    use crate::models::hotkey::KeySequence;
+
     let content = r#"
-version = 1
+version = 2
 
 [app]
 id = "chrome"
 name = "Chrome"
-default_priority = "Application"
+default_priority = "application"
 
-[app.app_os_name]
+[app.application_os_name]
 windows = "chrome.exe"
 macos = "com.google.Chrome"
 
@@ -40,10 +57,50 @@ name = "New tab"
 focus_state = "focused"
 cmd.windows = { mods = ["ctrl"], key = "t" }
 cmd.macos = { mods = ["cmd"], key = "t" }
+
+[actions.reopen_tab]
+name = "Reopen closed tab"
+aliases = ["Undo close tab", "Restore tab"]
+description = "Reopens the last tab you closed"
+keywords = ["history"]
+category = "Tabs"
+cmd.windows = { mods = ["ctrl", "shift"], key = "t" }
+
+[actions.save_all]
+name = "Save all"
+cmd.windows = [{ mods = ["ctrl"], key = "k" }, { mods = ["ctrl"], key = "s" }]
+cmd.linux = { sequence = [{ key = "g" }, { key = "i" }], delay_ms = 50 }
 "#;
 
     let cfg: Config = toml::from_str(content).expect("should deserialize");
     assert_eq!(cfg.app.id, "chrome");
     assert!(cfg.actions.contains_key("new_tab"));
+    assert!(cfg.actions["new_tab"].aliases.is_empty());
+
+    let reopen = &cfg.actions["reopen_tab"];
+    assert_eq!(reopen.aliases, vec!["Undo close tab", "Restore tab"]);
+    assert_eq!(
+        reopen.description.as_deref(),
+        Some("Reopens the last tab you closed")
+    );
+    assert_eq!(reopen.keywords, vec!["history"]);
+    assert_eq!(reopen.category.as_deref(), Some("Tabs"));
+
+    let save_all = &cfg.actions["save_all"].cmd;
+    let windows = KeySequence::from(save_all.windows.as_ref().unwrap());
+    assert_eq!(windows.to_string(), "Ctrl+K Ctrl+S");
+    let linux = KeySequence::from(save_all.linux.as_ref().unwrap());
+    assert_eq!((linux.to_string(), linux.delay_ms), ("G I".to_string(), 50));
     // println!("{cfg:?}")
 }
+
+#[test]
+fn parse_errors_point_at_the_line() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("broken.toml");
+    fs::write(&path, "version = 2\n\n[app]\nid = \n").unwrap();
+    let err = load_config(&path).unwrap_err();
+
+    assert_eq!(err.line, Some(4));
+    assert!(err.reason.starts_with("Could not parse config"));
+}
@@ -0,0 +1,215 @@
+// Reads an extension file of any known schema version into the current models::config.
+// Older versions are migrated with a deprecation warning for everything the author
+// should rename, newer versions are rejected
+
+use std::path::Path;
+
+use serde::Deserialize;
+use toml::Spanned;
+
+use crate::models::{
+    config::{Action, App, ByOs, Config, Priority, StepsByOs, CURRENT_VERSION},
+    config_v1::{ConfigV1, PriorityV1},
+    extension_error::ExtensionError,
+};
+
+#[derive(Deserialize)]
+struct Versioned {
+    version: Option<Spanned<i64>>,
+}
+
+// The config and its deprecation warnings
+pub fn parse(path: &Path, source: &str) -> Result<(Config, Vec<ExtensionError>), ExtensionError> {
+    let versioned: Versioned = from_str(path, source)?;
+    let Some(version) = versioned.version else {
+        return Err(ExtensionError::file(
+            path.to_path_buf(),
+            format!("No version, add `version = {CURRENT_VERSION}` at the top of the file"),
+        ));
+    };
+
+    match *version.get_ref() {
+        1 => Ok(migrate_v1(path, source, from_str(path, source)?)),
+        v if v == CURRENT_VERSION as i64 => Ok((from_str(path, source)?, vec![])),
+        v if v > CURRENT_VERSION as i64 => Err(ExtensionError::at(
+            path.to_path_buf(),
+            source,
+            version.span(),
+            format!(
+                "Schema version {v} is newer than this global_palette understands \
+                 (up to {CURRENT_VERSION}), update global_palette to load this file"
+            ),
+        )),
+        v => Err(ExtensionError::at(
+            path.to_path_buf(),
+            source,
+            version.span(),
+            format!("Unknown schema version {v}, use version = {CURRENT_VERSION}"),
+        )),
+    }
+}
+
+fn from_str<T: serde::de::DeserializeOwned>(
+    path: &Path,
+    source: &str,
+) -> Result<T, ExtensionError> {
+    toml::from_str(source).map_err(|e| {
+        let reason = format!("Could not parse config: {}", e.message());
+        match e.span() {
+            Some(span) => ExtensionError::at(path.to_path_buf(), source, span, reason),
+            None => ExtensionError::file(path.to_path_buf(), reason),
+        }
+    })
+}
+
+fn migrate_v1(path: &Path, source: &str, old: ConfigV1) -> (Config, Vec<ExtensionError>) {
+    let mut warnings = vec![ExtensionError::file(
+        path.to_path_buf(),
+        format!("Schema version 1 is deprecated, migrate to version = {CURRENT_VERSION}"),
+    )];
+    let mut priority = |old: &Spanned<PriorityV1>, action: Option<&str>| {
+        let (new, name) = migrate_priority(*old.get_ref());
+        let warning = ExtensionError::at(
+            path.to_path_buf(),
+            source,
+            old.span(),
+            format!("`{:?}` is the version 1 name, use `{name}`", old.get_ref()),
+        );
+        warnings.push(match action {
+            Some(action) => warning.in_action(action),
+            None => warning,
+        });
+        new
+    };
+
+    let app = App {
+        id: old.app.id,
+        name: old.app.name,
+        default_focus_state: old.app.default_focus_state,
+        default_priority: priority(&old.app.default_priority, None),
+        application_os_name: old.app.application_os_name,
+    };
+
+    // In file order, so the warnings are too
+    let mut actions: Vec<_> = old.actions.into_iter().collect();
+    actions.sort_by_key(|(key, _)| key.span().start);
+    let actions = actions
+        .into_iter()
+        .map(|(key, action)| {
+            let new = Action {
+                name: action.name,
+                aliases: action.aliases,
+                description: action.description,
+                keywords: action.keywords,
+                category: action.category,
+                focus_state: action.focus_state,
+                priority: action
+                    .priority
+                    .as_ref()
+                    .map(|p| priority(p, Some(key.get_ref()))),
+                cmd: action.cmd,
+                steps: StepsByOs::default(),
+                launch: ByOs::default(),
+                open: ByOs::default(),
+                shell: ByOs::default(),
+                type_text: ByOs::default(),
+            };
+            (key, new)
+        })
+        .collect();
+
+    let config = Config {
+        version: CURRENT_VERSION,
+        app,
+        actions,
+    };
+    (config, warnings)
+}
+
+// With the version 2 spelling, for the warning
+fn migrate_priority(old: PriorityV1) -> (Priority, &'static str) {
+    match old {
+        PriorityV1::OSReserved => (Priority::OSReserved, "os_reserved"),
+        PriorityV1::GlobalRemapper => (Priority::GlobalRemapper, "global_remapper"),
+        PriorityV1::OSGlobal => (Priority::OSGlobal, "os_global"),
+        PriorityV1::UserOverrides => (Priority::UserOverrides, "user_overrides"),
+        PriorityV1::Application => (Priority::Application, "application"),
+        PriorityV1::ApplicationExtensions => {
+            (Priority::ApplicationExtensions, "application_extensions")
+        }
+        PriorityV1::DocumentOrWebApp => (Priority::DocumentOrWebApp, "document_or_web_app"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const V1: &str = r#"version = 1
+
+[app]
+id = "chrome"
+name = "Chrome"
+default_priority = "Application"
+
+[app.application_os_name]
+windows = "chrome.exe"
+
+[actions.new_tab]
+name = "New tab"
+priority = "ApplicationExtensions"
+cmd.windows = { mods = ["ctrl"], key = "t" }
+"#;
+
+    fn messages(warnings: &[ExtensionError]) -> Vec<String> {
+        warnings.iter().map(ToString::to_string).collect()
+    }
+
+    #[test]
+    fn migrates_version_1_with_warnings() {
+        let (config, warnings) = parse(Path::new("chrome.toml"), V1).unwrap();
+
+        assert_eq!(config.version, CURRENT_VERSION);
+        assert!(matches!(config.app.default_priority, Priority::Application));
+        assert!(matches!(
+            config.actions["new_tab"].priority,
+            Some(Priority::ApplicationExtensions)
+        ));
+        assert_eq!(
+            messages(&warnings),
+            vec![
+                "chrome.toml: Schema version 1 is deprecated, migrate to version = 2",
+                "chrome.toml:6: `Application` is the version 1 name, use `application`",
+                "chrome.toml:13: new_tab: `ApplicationExtensions` is the version 1 name, use `application_extensions`",
+            ]
+        );
+    }
+
+    #[test]
+    fn current_version_has_no_warnings() {
+        let v2 = V1
+            .replace("version = 1", "version = 2")
+            .replace("\"ApplicationExtensions\"", "\"application_extensions\"")
+            .replace("\"Application\"", "\"application\"");
+        let (_, warnings) = parse(Path::new("chrome.toml"), &v2).unwrap();
+        assert!(warnings.is_empty());
+
+        // Version 1 names are not accepted in version 2
+        let mixed = V1.replace("version = 1", "version = 2");
+        assert!(parse(Path::new("chrome.toml"), &mixed).is_err());
+    }
+
+    #[test]
+    fn rejects_missing_and_future_versions() {
+        let future = V1.replace("version = 1", "version = 7");
+        let err = parse(Path::new("chrome.toml"), &future).unwrap_err();
+        assert_eq!(err.line, Some(1));
+        assert!(err
+            .reason
+            .contains("newer than this global_palette understands"));
+
+        let missing = V1.replace("version = 1", "");
+        let err = parse(Path::new("chrome.toml"), &missing).unwrap_err();
+        assert!(err.reason.starts_with("No version"));
+    }
+}
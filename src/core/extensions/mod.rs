@@ -1 +1,6 @@
+#[allow(clippy::module_inception)]
 pub mod extensions;
+pub mod migration;
+pub mod sources;
+pub mod validation;
+pub mod watcher;
@@ -0,0 +1,179 @@
+// Turns the search_dirs and extensions of config.toml into the files to load
+
+use std::path::{Path, PathBuf};
+
+use log::warn;
+
+use crate::{
+    core::extensions::watcher::normalize,
+    models::app_config::{AppConfig, ExtensionEntry},
+    platform::platform_interface::{system_extensions_dir, user_extensions_dir},
+};
+
+#[derive(Debug, Clone)]
+pub struct ExtensionSources {
+    dirs: Vec<PathBuf>, // Lowest precedence first
+    entries: Vec<ExtensionEntry>,
+}
+
+impl ExtensionSources {
+    pub fn new(app_config: &AppConfig) -> ExtensionSources {
+        let dirs = app_config
+            .search_dirs
+            .iter()
+            .filter_map(|dir| match dir.as_str() {
+                "{system}" => system_extensions_dir(),
+                "{user}" => user_extensions_dir(),
+                _ => Some(PathBuf::from(dir)),
+            })
+            .map(|dir| normalize(&dir))
+            .collect();
+
+        let entries = if app_config.extensions.is_empty() {
+            vec![ExtensionEntry::Path("*.toml".into())]
+        } else {
+            app_config.extensions.clone()
+        };
+
+        ExtensionSources { dirs, entries }
+    }
+
+    // Every enabled extension file with its precedence level, lowest precedence
+    // first. A later entry wins over an earlier one, within an entry a later search
+    // dir wins. The level is the search dir, a file from a later dir may override
+    // an app of an earlier one, two files of the same dir may not
+    pub fn resolve(&self) -> Vec<(PathBuf, usize)> {
+        let mut files: Vec<(PathBuf, usize)> = vec![];
+        let mut disabled: Vec<PathBuf> = vec![];
+
+        for (entry_idx, entry) in self.entries.iter().enumerate() {
+            let matched = self.matches(entry.path(), entry_idx);
+            if matched.is_empty() {
+                warn!("Extension {} matched no file", entry.path());
+            }
+
+            for (file, level) in matched {
+                if !entry.enabled() {
+                    disabled.push(file);
+                    continue;
+                }
+                files.retain(|(f, _)| *f != file);
+                files.push((file, level));
+            }
+        }
+
+        files.retain(|(f, _)| !disabled.contains(f));
+        files
+    }
+
+    // Folders to watch for new, edited and removed extension files
+    pub fn watch_dirs(&self) -> Vec<PathBuf> {
+        let mut dirs: Vec<PathBuf> = self.dirs.iter().filter(|d| d.is_dir()).cloned().collect();
+        for entry in &self.entries {
+            let path = Path::new(entry.path());
+            if !path.is_absolute() {
+                continue;
+            }
+            // Deepest folder without glob characters
+            let base = path
+                .ancestors()
+                .skip(1)
+                .find(|a| !a.to_string_lossy().contains(['*', '?', '[']));
+            if let Some(base) = base.filter(|b| b.is_dir()) {
+                dirs.push(base.to_path_buf());
+            }
+        }
+        dirs.sort();
+        dirs.dedup();
+        dirs
+    }
+
+    // Every absolute entry is a level of its own
+    fn matches(&self, pattern: &str, entry_idx: usize) -> Vec<(PathBuf, usize)> {
+        if Path::new(pattern).is_absolute() {
+            let level = self.dirs.len() + entry_idx;
+            return glob_files(Path::new(pattern))
+                .into_iter()
+                .map(|file| (file, level))
+                .collect();
+        }
+        self.dirs
+            .iter()
+            .enumerate()
+            .flat_map(|(level, dir)| {
+                glob_files(&dir.join(pattern))
+                    .into_iter()
+                    .map(move |file| (file, level))
+            })
+            .collect()
+    }
+}
+
+// Sorted by name, so the precedence inside a glob does not depend on the OS
+fn glob_files(pattern: &Path) -> Vec<PathBuf> {
+    let pattern = pattern.to_string_lossy();
+    match glob::glob(&pattern) {
+        Ok(paths) => paths
+            .filter_map(Result::ok)
+            .filter(|p| p.is_file())
+            .map(|p| normalize(&p))
+            .collect(),
+        Err(e) => {
+            warn!("Invalid extension pattern {pattern}: {e}");
+            vec![]
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    fn sources(dirs: &[&Path], extensions: &str) -> ExtensionSources {
+        let mut config: AppConfig = toml::from_str(&format!("extensions = {extensions}")).unwrap();
+        config.search_dirs = dirs.iter().map(|d| d.display().to_string()).collect();
+        ExtensionSources::new(&config)
+    }
+
+    #[test]
+    fn order_of_entries_and_dirs_is_precedence() {
+        let temp = tempfile::tempdir().unwrap();
+        let root = temp.path();
+        let (team, personal) = (root.join("team"), root.join("personal"));
+        fs::create_dir_all(team.join("pack")).unwrap();
+        fs::create_dir_all(&personal).unwrap();
+        for file in [
+            "chrome.toml",
+            "pack/slack.toml",
+            "pack/zoom.toml",
+            "notes.txt",
+        ] {
+            fs::write(team.join(file), "").unwrap();
+        }
+        fs::write(personal.join("chrome.toml"), "").unwrap();
+        let dirs = [team.as_path(), personal.as_path()];
+
+        let resolved = sources(
+            &dirs,
+            r#"["chrome.toml", "pack/*.toml", { path = "pack/zoom.toml", enabled = false }]"#,
+        )
+        .resolve();
+        assert_eq!(
+            resolved,
+            vec![
+                (normalize(&team.join("chrome.toml")), 0),
+                (normalize(&personal.join("chrome.toml")), 1),
+                (normalize(&team.join("pack/slack.toml")), 0),
+            ]
+        );
+
+        // Every .toml when nothing is listed, the personal chrome.toml still wins
+        let resolved = sources(&dirs, "[]").resolve();
+        assert_eq!(
+            resolved.last(),
+            Some(&(normalize(&personal.join("chrome.toml")), 1))
+        );
+        assert_eq!(resolved.len(), 2);
+    }
+}
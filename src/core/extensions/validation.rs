@@ -0,0 +1,237 @@
+// Checks an extension file for everything that would drop an action or the whole
+// app, so the author sees every problem at once instead of the first one.
+// An app or action that is not there for the current OS is a warning, most files
+// only cover some of them. An action that is broken is an error
+
+use crate::{
+    core::{
+        extensions::extensions::ExtensionFile,
+        registry::registry::{run_target, MasterRegistry},
+        snippets,
+    },
+    models::{
+        action::{FocusState, Os},
+        config::Step,
+        extension_error::ExtensionError,
+    },
+};
+
+// All in file order. No errors means every action that has a binding for the
+// current OS is loaded
+#[derive(Debug, Default)]
+pub struct Validation {
+    pub errors: Vec<ExtensionError>,
+    pub warnings: Vec<ExtensionError>,
+    pub runs: Vec<RunStep>,
+}
+
+// A `run` step can name an action of an other file, so it is only checked once
+// every file is loaded. `error` is reported when `target` is not one of them
+#[derive(Debug, Clone)]
+pub struct RunStep {
+    pub target: String, // "app/action"
+    pub error: ExtensionError,
+}
+
+// Run steps of the loaded files that point at no loaded action
+pub fn unresolved_runs(runs: &[RunStep], registry: &MasterRegistry) -> Vec<ExtensionError> {
+    runs.iter()
+        .filter(|run| registry.find_action(&run.target).is_none())
+        .map(|run| run.error.clone())
+        .collect()
+}
+
+pub fn validate(file: &ExtensionFile, current_os: Os) -> Validation {
+    let config = &file.config;
+    let os = current_os.config_key();
+    let mut errors = vec![];
+    let mut warnings = vec![];
+    let mut runs = vec![];
+
+    // Global actions do not need a window
+    let needs_window = config.actions.values().any(|action| {
+        action.focus_state.or(config.app.default_focus_state) != Some(FocusState::Global)
+    });
+    if needs_window && config.app.application_os_name.for_os(current_os).is_none() {
+        warnings.push(ExtensionError::file(
+            file.path.clone(),
+            format!("No app.application_os_name.{os}, the app cannot be matched to a window"),
+        ));
+    }
+
+    let mut actions: Vec<_> = config.actions.iter().collect();
+    actions.sort_by_key(|(key, _)| key.span().start);
+
+    for (key, action) in actions {
+        let error = |reason: String| {
+            ExtensionError::at(file.path.clone(), &file.source, key.span(), reason)
+                .in_action(key.get_ref())
+        };
+
+        let kinds: Vec<&str> = [
+            ("cmd", action.cmd.for_os(current_os).is_some()),
+            ("steps", action.steps.for_os(current_os).is_some()),
+            ("launch", action.launch.for_os(current_os).is_some()),
+            ("open", action.open.for_os(current_os).is_some()),
+            ("shell", action.shell.for_os(current_os).is_some()),
+            ("type_text", action.type_text.for_os(current_os).is_some()),
+        ]
+        .into_iter()
+        .filter_map(|(kind, set)| set.then_some(kind))
+        .collect();
+        match kinds.as_slice() {
+            [] => warnings.push(error(format!(
+                "No cmd, steps, launch, open, shell or type_text for {os}, the action is skipped on this OS"
+            ))),
+            ["cmd"]
+                if action
+                    .cmd
+                    .for_os(current_os)
+                    .is_some_and(|b| b.chords().is_empty()) =>
+            {
+                errors.push(error(format!("cmd.{os} is an empty key sequence")))
+            }
+            ["steps"] if action.steps.for_os(current_os).is_some_and(Vec::is_empty) => {
+                errors.push(error(format!("steps.{os} has no steps")))
+            }
+            ["steps"] => {
+                let steps = action.steps.for_os(current_os).into_iter().flatten();
+                for (idx, step) in steps.enumerate() {
+                    match step {
+                        Step::Send(binding) if binding.chords().is_empty() => errors.push(error(
+                            format!("steps.{os}: step {} sends an empty key sequence", idx + 1),
+                        )),
+                        Step::Run(id) => runs.push(RunStep {
+                            target: run_target(id, &config.app.id),
+                            error: error(format!(
+                                "steps.{os}: step {} runs `{id}`, which is not a loaded action",
+                                idx + 1
+                            )),
+                        }),
+                        _ => {}
+                    }
+                }
+            }
+            ["type_text"] => {
+                let text = action.type_text.for_os(current_os).map(|t| t.text.as_str());
+                if let Some(Err(e)) = text.map(snippets::parse) {
+                    errors.push(error(format!("type_text.{os}: {e}")))
+                }
+            }
+            [_] => {}
+            _ => errors.push(error(format!(
+                "{} are all set for {os}, an action does only one of them",
+                kinds.join(", ")
+            ))),
+        }
+        if action.focus_state.is_none() && config.app.default_focus_state.is_none() {
+            errors.push(error(
+                "No focus_state and no app.default_focus_state".to_string(),
+            ));
+        }
+    }
+
+    Validation {
+        errors,
+        warnings,
+        runs,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CONFIG: &str = r#"version = 2
+
+[app]
+id = "chrome"
+name = "Chrome"
+default_priority = "application"
+
+[app.application_os_name]
+windows = "chrome.exe"
+
+[actions.new_tab]
+name = "New tab"
+focus_state = "focused"
+cmd.windows = { mods = ["ctrl"], key = "t" }
+
+[actions.close_tab]
+name = "Close tab"
+cmd.linux = { mods = ["ctrl"], key = "w" }
+"#;
+
+    fn file() -> ExtensionFile {
+        ExtensionFile {
+            path: "chrome.toml".into(),
+            source: CONFIG.into(),
+            config: toml::from_str(CONFIG).unwrap(),
+            deprecations: vec![],
+        }
+    }
+
+    #[test]
+    fn valid_file_has_no_errors() {
+        let mut file = file();
+        file.config
+            .actions
+            .retain(|key, _| key.get_ref() == "new_tab");
+        let validation = validate(&file, Os::Windows);
+        assert!(validation.errors.is_empty());
+        assert!(validation.warnings.is_empty());
+    }
+
+    #[test]
+    fn checks_the_steps_of_macros() {
+        let source = format!(
+            r#"{CONFIG}
+[actions.two_tabs]
+name = "Two tabs"
+focus_state = "focused"
+steps.windows = [{{ run = "new_tab" }}, {{ send = [] }}, {{ run = "slack/mute" }}]
+"#
+        );
+        let file = ExtensionFile {
+            path: "chrome.toml".into(),
+            config: toml::from_str(&source).unwrap(),
+            source,
+            deprecations: vec![],
+        };
+        let validation = validate(&file, Os::Windows);
+
+        // close_tab has its own problem
+        let errors: Vec<String> = validation.errors[1..]
+            .iter()
+            .map(ToString::to_string)
+            .collect();
+        assert_eq!(
+            errors,
+            vec!["chrome.toml:20: two_tabs: steps.windows: step 2 sends an empty key sequence"]
+        );
+        let targets: Vec<&str> = validation.runs.iter().map(|r| r.target.as_str()).collect();
+        assert_eq!(targets, vec!["chrome/new_tab", "slack/mute"]);
+        assert_eq!(validation.runs[1].error.line, Some(20));
+    }
+
+    #[test]
+    fn collects_every_problem_of_the_file() {
+        let validation = validate(&file(), Os::Linux);
+        let to_strings = |problems: &[ExtensionError]| -> Vec<String> {
+            problems.iter().map(ToString::to_string).collect()
+        };
+
+        assert_eq!(
+            to_strings(&validation.errors),
+            vec!["chrome.toml:16: close_tab: No focus_state and no app.default_focus_state"]
+        );
+        // Windows only actions are fine, they are just not there on Linux
+        assert_eq!(
+            to_strings(&validation.warnings),
+            vec![
+                "chrome.toml: No app.application_os_name.linux, the app cannot be matched to a window",
+                "chrome.toml:11: new_tab: No cmd, steps, launch, open, shell or type_text for linux, the action is skipped on this OS",
+            ]
+        );
+    }
+}
@@ -0,0 +1,99 @@
+// Watches the extension folders so edits apply without restarting the palette
+
+use std::{
+    collections::BTreeSet,
+    path::{Path, PathBuf},
+    sync::mpsc::{self, RecvTimeoutError},
+    thread,
+    time::Duration,
+};
+
+use log::{error, warn};
+use notify::{EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+
+// Editors save in several steps (truncate, write, rename), wait for them to settle
+const SETTLE: Duration = Duration::from_millis(200);
+
+// Stops watching when dropped
+pub struct ExtensionWatcher {
+    _watcher: RecommendedWatcher,
+}
+
+// Calls `on_change` with the files that were added, edited or removed.
+// `wanted` picks the files we care about among everything in `dirs`
+pub fn watch_extensions(
+    dirs: &[PathBuf],
+    wanted: impl Fn(&Path) -> bool + Send + 'static,
+    mut on_change: impl FnMut(Vec<PathBuf>) + Send + 'static,
+) -> Result<ExtensionWatcher, String> {
+    let (tx, rx) = mpsc::channel();
+    let mut watcher =
+        notify::recommended_watcher(move |res: notify::Result<notify::Event>| match res {
+            Ok(event) if !matches!(event.kind, EventKind::Access(_)) => {
+                let _ = tx.send(event.paths);
+            }
+            Ok(_) => {}
+            Err(e) => warn!("Extension watcher error: {e}"),
+        })
+        .map_err(|e| format!("Could not watch the extensions: {e}"))?;
+
+    for dir in dirs {
+        watcher
+            .watch(dir, RecursiveMode::Recursive)
+            .map_err(|e| format!("Could not watch {dir:?}: {e}"))?;
+    }
+
+    // Ends once the watcher is dropped, that drops `tx`
+    thread::spawn(move || {
+        let mut changed = BTreeSet::new();
+        loop {
+            match rx.recv_timeout(SETTLE) {
+                Ok(paths) => changed.extend(paths.into_iter().filter(|p| wanted(p))),
+                Err(RecvTimeoutError::Timeout) if !changed.is_empty() => {
+                    on_change(std::mem::take(&mut changed).into_iter().collect());
+                }
+                Err(RecvTimeoutError::Timeout) => {}
+                Err(RecvTimeoutError::Disconnected) => break,
+            }
+        }
+    });
+
+    Ok(ExtensionWatcher { _watcher: watcher })
+}
+
+// Absolute, so paths from the config and from the watcher compare equal
+pub fn normalize(path: &Path) -> PathBuf {
+    std::path::absolute(path).unwrap_or_else(|e| {
+        error!("Could not resolve {path:?}: {e}");
+        path.to_path_buf()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn reports_edited_files_once_settled() {
+        let temp = tempfile::tempdir().unwrap();
+        let dir = normalize(temp.path());
+
+        let (tx, rx) = mpsc::channel();
+        let _watcher = watch_extensions(
+            std::slice::from_ref(&dir),
+            |p| p.extension().is_some_and(|e| e == "toml"),
+            move |paths| {
+                let _ = tx.send(paths);
+            },
+        )
+        .unwrap();
+
+        fs::write(dir.join("chrome.toml"), "version = 1").unwrap();
+        fs::write(dir.join("chrome.toml"), "version = 2").unwrap();
+        fs::write(dir.join("notes.txt"), "ignored").unwrap();
+
+        let changed = rx.recv_timeout(Duration::from_secs(5)).unwrap();
+        assert_eq!(changed, vec![dir.join("chrome.toml")]);
+    }
+}
@@ -0,0 +1,175 @@
+// Usage history used for frecency (frequency + recency) boosting of palette results
+// See "Recency" in Design/01_fuzzy_scorer.md
+
+use std::{
+    collections::HashMap,
+    fs,
+    path::Path,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use serde::{Deserialize, Serialize};
+
+// Every use counts half as much after this many seconds (3 days)
+const FREQUENCY_HALF_LIFE_SECS: f64 = 3.0 * 24.0 * 60.0 * 60.0;
+// Extra weight for something that was just run, halves every 10 minutes
+const RECENCY_HALF_LIFE_SECS: f64 = 10.0 * 60.0;
+const RECENCY_BONUS: f64 = 10.0;
+// Frecency at which the search boost is capped (score doubled)
+const MAX_BOOST_FRECENCY: f64 = 10.0;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct UsageEntry {
+    pub id: String, // "app_id/action_id", see UnitAction::id
+    pub count: u32,
+    pub score: f64,     // Decayed use count, as of last_used
+    pub last_used: u64, // Unix seconds
+}
+
+impl UsageEntry {
+    pub fn frecency(&self, now: u64) -> f64 {
+        let age = now.saturating_sub(self.last_used) as f64;
+        let frequency = self.score * decay(age, FREQUENCY_HALF_LIFE_SECS);
+        let recency = RECENCY_BONUS * decay(age, RECENCY_HALF_LIFE_SECS);
+        frequency + recency
+    }
+}
+
+fn decay(age_secs: f64, half_life_secs: f64) -> f64 {
+    0.5f64.powf(age_secs / half_life_secs)
+}
+
+pub fn now_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+// On disk format
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct HistoryFile {
+    #[serde(default)]
+    entries: Vec<UsageEntry>,
+}
+
+#[derive(Debug, Default)]
+pub struct UsageHistory {
+    entries: HashMap<String, UsageEntry>,
+}
+
+impl UsageHistory {
+    // A missing file is an empty history
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<UsageHistory, String> {
+        let path = path.as_ref();
+        if !path.exists() {
+            return Ok(UsageHistory::default());
+        }
+
+        let content =
+            fs::read_to_string(path).map_err(|e| format!("Could not read history: {e}"))?;
+        let file: HistoryFile =
+            toml::from_str(&content).map_err(|e| format!("Could not parse history: {e}"))?;
+
+        Ok(UsageHistory {
+            entries: file
+                .entries
+                .into_iter()
+                .map(|e| (e.id.clone(), e))
+                .collect(),
+        })
+    }
+
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<(), String> {
+        let mut entries: Vec<UsageEntry> = self.entries.values().cloned().collect();
+        entries.sort_by_key(|e| std::cmp::Reverse(e.last_used));
+
+        let content = toml::to_string(&HistoryFile { entries })
+            .map_err(|e| format!("Could not serialize history: {e}"))?;
+        fs::write(path, content).map_err(|e| format!("Could not write history: {e}"))
+    }
+
+    pub fn record(&mut self, id: &str, now: u64) {
+        let entry = self
+            .entries
+            .entry(id.to_string())
+            .or_insert_with(|| UsageEntry {
+                id: id.to_string(),
+                count: 0,
+                score: 0.0,
+                last_used: now,
+            });
+
+        // Bring the old score forward to now before adding this use
+        let age = now.saturating_sub(entry.last_used) as f64;
+        entry.score = entry.score * decay(age, FREQUENCY_HALF_LIFE_SECS) + 1.0;
+        entry.count += 1;
+        entry.last_used = now;
+    }
+
+    pub fn frecency(&self, id: &str, now: u64) -> f64 {
+        self.entries.get(id).map_or(0.0, |e| e.frecency(now))
+    }
+
+    // Percentage added on top of a search score, 0 for never used up to 100
+    pub fn boost(&self, id: &str, now: u64) -> i32 {
+        let frecency = self.frecency(id, now).min(MAX_BOOST_FRECENCY);
+        (frecency / MAX_BOOST_FRECENCY * 100.0) as i32
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MINUTE: u64 = 60;
+    const DAY: u64 = 24 * 60 * MINUTE;
+
+    #[test]
+    fn unused_actions_have_no_boost() {
+        let history = UsageHistory::default();
+        assert_eq!(history.frecency("chrome/new_tab", 1000), 0.0);
+        assert_eq!(history.boost("chrome/new_tab", 1000), 0);
+    }
+
+    #[test]
+    fn recent_beats_frequent_but_old() {
+        let now = 30 * DAY;
+        let mut history = UsageHistory::default();
+        for _ in 0..5 {
+            history.record("chrome/new_tab", now - 7 * DAY);
+        }
+        history.record("chrome/close_tab", now - 2 * MINUTE);
+
+        assert!(
+            history.frecency("chrome/close_tab", now) > history.frecency("chrome/new_tab", now)
+        );
+    }
+
+    #[test]
+    fn score_decays_over_time() {
+        let mut history = UsageHistory::default();
+        history.record("chrome/new_tab", 0);
+        let fresh = history.frecency("chrome/new_tab", 0);
+        let week_later = history.frecency("chrome/new_tab", 7 * DAY);
+        assert!(week_later < fresh);
+        assert!(week_later > 0.0);
+    }
+
+    #[test]
+    fn round_trips_through_disk() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("history.toml");
+        let mut history = UsageHistory::default();
+        history.record("chrome/new_tab", 100);
+        history.record("chrome/new_tab", 200);
+        history.save(&path).expect("should save");
+
+        let loaded = UsageHistory::load(&path).expect("should load");
+        assert_eq!(
+            loaded.entries.get("chrome/new_tab"),
+            history.entries.get("chrome/new_tab")
+        );
+        assert_eq!(loaded.entries["chrome/new_tab"].count, 2);
+    }
+}
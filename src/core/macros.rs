@@ -0,0 +1,311 @@
+// Macro actions. `run` steps are expanded on the caller's thread, where the
+// registry is, then the steps run on their own thread so waits do not block the UI
+
+use std::{
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Mutex,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+use log::info;
+
+use crate::{
+    core::{executor::FOCUS_SETTLE, registry::registry::UnitAction, snippets},
+    models::action::{ActionKind, MacroStep},
+    platform::{clipboard::Clipboard, key_sender::KeySender, spawner::Spawner},
+};
+
+// How often a wait checks for cancellation
+const CANCEL_POLL: Duration = Duration::from_millis(10);
+
+// Replaces every `run` step with the steps of that action, a shortcut becomes a
+// `send`. `id` is the macro itself, to catch a macro that runs itself
+pub fn expand(
+    id: &str,
+    steps: &[MacroStep],
+    lookup: &dyn Fn(&str) -> Option<UnitAction>,
+) -> Result<Vec<MacroStep>, String> {
+    let mut expanded = vec![];
+    expand_into(&mut vec![id.to_string()], steps, lookup, &mut expanded)?;
+    Ok(expanded)
+}
+
+fn expand_into(
+    stack: &mut Vec<String>,
+    steps: &[MacroStep],
+    lookup: &dyn Fn(&str) -> Option<UnitAction>,
+    expanded: &mut Vec<MacroStep>,
+) -> Result<(), String> {
+    for step in steps {
+        let MacroStep::Run(id) = step else {
+            expanded.push(step.clone());
+            continue;
+        };
+        if stack.contains(id) {
+            return Err(format!("Macro runs itself: {} -> {id}", stack.join(" -> ")));
+        }
+
+        let action = lookup(id).ok_or_else(|| format!("No action {id} to run"))?;
+        match &action.kind {
+            ActionKind::Shortcut(sequence) => expanded.push(MacroStep::Send(sequence.clone())),
+            ActionKind::Spawn(spawn) => expanded.push(MacroStep::Spawn(spawn.clone())),
+            ActionKind::TypeText(snippet) => expanded.push(MacroStep::TypeText(snippet.clone())),
+            ActionKind::Macro(inner) => {
+                stack.push(id.clone());
+                expand_into(stack, inner, lookup, expanded)?;
+                stack.pop();
+            }
+        }
+    }
+    Ok(())
+}
+
+// Expanded steps in order. Stops before the next step, or during a wait, once
+// `cancel` is set. `focus` gives focus to a process, see focus_process
+pub fn run_steps(
+    steps: &[MacroStep],
+    sender: &Mutex<Box<dyn KeySender + Send>>,
+    spawner: &Mutex<Box<dyn Spawner + Send>>,
+    clipboard: &Mutex<Box<dyn Clipboard + Send>>,
+    focus: &dyn Fn(&str) -> bool,
+    cancel: &AtomicBool,
+) -> Result<(), String> {
+    let lock = || {
+        sender
+            .lock()
+            .map_err(|_| "Key sender is poisoned".to_string())
+    };
+    let lock_spawner = || {
+        spawner
+            .lock()
+            .map_err(|_| "Spawner is poisoned".to_string())
+    };
+
+    for step in steps {
+        if cancel.load(Ordering::Relaxed) {
+            info!("Macro cancelled before {step}");
+            return Ok(());
+        }
+
+        match step {
+            // Each chord is released before the next one is pressed. The sender
+            // is free during the delay
+            MacroStep::Send(sequence) => {
+                for (idx, chord) in sequence.chords.iter().enumerate() {
+                    if idx > 0 && !wait(sequence.delay_ms, cancel) {
+                        info!("Macro cancelled during {step}");
+                        return Ok(());
+                    }
+                    lock()?.send_shortcut(chord)?;
+                }
+            }
+            MacroStep::Type(text) => lock()?.type_text(text)?,
+            MacroStep::Wait(ms) => {
+                wait(*ms, cancel);
+            }
+            MacroStep::Focus(process) => {
+                if !focus(process) {
+                    return Err(format!("No window of {process} to focus"));
+                }
+                thread::sleep(FOCUS_SETTLE);
+            }
+            MacroStep::Spawn(spawn) => lock_spawner()?.spawn(spawn)?,
+            MacroStep::TypeText(snippet) => {
+                let mut clipboard = clipboard
+                    .lock()
+                    .map_err(|_| "Clipboard is poisoned".to_string())?;
+                snippets::type_snippet(snippet, &mut **lock()?, &mut **clipboard)?
+            }
+            MacroStep::Run(id) => return Err(format!("Step run {id} was not expanded")),
+        }
+    }
+    Ok(())
+}
+
+// False when cancelled before the time is up
+fn wait(ms: u64, cancel: &AtomicBool) -> bool {
+    let until = Instant::now() + Duration::from_millis(ms);
+    while Instant::now() < until && !cancel.load(Ordering::Relaxed) {
+        thread::sleep(CANCEL_POLL.min(until - Instant::now()));
+    }
+    !cancel.load(Ordering::Relaxed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        models::{
+            action::{FocusState, Priority, Spawn},
+            hotkey::{HotkeyModifiers, Key, KeySequence, KeyboardShortcut, Modifier},
+        },
+        platform::{
+            clipboard::MemoryClipboard,
+            key_sender::{KeyCode, KeyEvent, RecordingKeySender},
+            spawner::RecordingSpawner,
+        },
+    };
+
+    fn chord(key: Key) -> KeySequence {
+        KeyboardShortcut {
+            modifier: HotkeyModifiers::default(),
+            key,
+        }
+        .into()
+    }
+
+    fn action(id: &str, kind: ActionKind) -> UnitAction {
+        let (app_id, action_id) = id.split_once('/').unwrap();
+        UnitAction {
+            app_id: app_id.into(),
+            app_name: app_id.into(),
+            action_id: action_id.into(),
+            action_name: action_id.into(),
+            focus_state: FocusState::Focused,
+            shortcut: kind.shortcut().map(ToString::to_string),
+            kind,
+            aliases: vec![],
+            description: None,
+            keywords: vec![],
+            category: None,
+            priority: Priority::Application,
+        }
+    }
+
+    fn lookup(id: &str) -> Option<UnitAction> {
+        let kind = match id {
+            "chrome/new_tab" => ActionKind::Shortcut(chord(Key::KeyT)),
+            "chrome/two_tabs" => ActionKind::Macro(vec![
+                MacroStep::Run("chrome/new_tab".into()),
+                MacroStep::Run("chrome/new_tab".into()),
+            ]),
+            "chrome/loop" => ActionKind::Macro(vec![MacroStep::Run("chrome/loop".into())]),
+            "chrome/docs" => ActionKind::Spawn(Spawn::Open("https://docs.rs".into())),
+            _ => return None,
+        };
+        Some(action(id, kind))
+    }
+
+    #[test]
+    fn expands_run_steps_recursively() {
+        let steps = [
+            MacroStep::Run("chrome/two_tabs".into()),
+            MacroStep::Wait(5),
+            MacroStep::Run("chrome/docs".into()),
+        ];
+        assert_eq!(
+            expand("chrome/macro", &steps, &lookup).unwrap(),
+            vec![
+                MacroStep::Send(chord(Key::KeyT)),
+                MacroStep::Send(chord(Key::KeyT)),
+                MacroStep::Wait(5),
+                MacroStep::Spawn(Spawn::Open("https://docs.rs".into())),
+            ]
+        );
+
+        let looped = [MacroStep::Run("chrome/loop".into())];
+        assert!(expand("chrome/macro", &looped, &lookup).is_err());
+        let missing = [MacroStep::Run("chrome/missing".into())];
+        assert!(expand("chrome/macro", &missing, &lookup).is_err());
+    }
+
+    #[test]
+    fn runs_steps_until_cancelled() {
+        let recorder = RecordingKeySender::default();
+        let sender: Mutex<Box<dyn KeySender + Send>> = Mutex::new(Box::new(recorder.clone()));
+        let spawns = RecordingSpawner::default();
+        let spawner: Mutex<Box<dyn Spawner + Send>> = Mutex::new(Box::new(spawns.clone()));
+        let clipboard: Mutex<Box<dyn Clipboard + Send>> =
+            Mutex::new(Box::new(MemoryClipboard::default()));
+        let steps = [
+            MacroStep::Send(chord(Key::KeyT)),
+            MacroStep::Wait(1),
+            MacroStep::Type("a".into()),
+            MacroStep::Spawn(Spawn::Shell("make".into())),
+        ];
+        let run = |steps: &[MacroStep], focus: &dyn Fn(&str) -> bool, cancel: bool| {
+            run_steps(
+                steps,
+                &sender,
+                &spawner,
+                &clipboard,
+                focus,
+                &AtomicBool::new(cancel),
+            )
+        };
+
+        run(&steps, &|_| true, false).unwrap();
+        let a = KeyCode::Key(Key::KeyA);
+        assert_eq!(recorder.events()[2..], [KeyEvent::Down(a), KeyEvent::Up(a)]);
+        assert_eq!(spawns.spawned(), [Spawn::Shell("make".into())]);
+
+        run(&steps, &|_| true, true).unwrap();
+        assert_eq!(recorder.events().len(), 4);
+
+        let focus = [MacroStep::Focus("chrome".into())];
+        assert!(run(&focus, &|_| false, false).is_err());
+    }
+
+    #[test]
+    fn sends_every_chord_of_a_sequence_unless_cancelled() {
+        let recorder = RecordingKeySender::default();
+        let sender: Mutex<Box<dyn KeySender + Send>> = Mutex::new(Box::new(recorder.clone()));
+        let spawner: Mutex<Box<dyn Spawner + Send>> =
+            Mutex::new(Box::new(RecordingSpawner::default()));
+        let clipboard: Mutex<Box<dyn Clipboard + Send>> =
+            Mutex::new(Box::new(MemoryClipboard::default()));
+        let ctrl = |key| KeyboardShortcut {
+            modifier: HotkeyModifiers {
+                control: true,
+                ..Default::default()
+            },
+            key,
+        };
+        let steps = [MacroStep::Send(KeySequence {
+            chords: vec![ctrl(Key::KeyK), ctrl(Key::KeyS)],
+            delay_ms: 1,
+        })];
+        let run = |cancel: &AtomicBool| {
+            run_steps(&steps, &sender, &spawner, &clipboard, &|_| true, cancel)
+        };
+
+        run(&AtomicBool::new(false)).unwrap();
+        let control = KeyCode::Modifier(Modifier::Control);
+        let (k, s) = (KeyCode::Key(Key::KeyK), KeyCode::Key(Key::KeyS));
+        assert_eq!(
+            recorder.events(),
+            vec![
+                KeyEvent::Down(control),
+                KeyEvent::Down(k),
+                KeyEvent::Up(k),
+                KeyEvent::Up(control),
+                KeyEvent::Down(control),
+                KeyEvent::Down(s),
+                KeyEvent::Up(s),
+                KeyEvent::Up(control),
+            ]
+        );
+
+        // Cancelled during the delay, the second chord is never pressed
+        let cancel = AtomicBool::new(false);
+        let sent = recorder.events().len();
+        thread::scope(|scope| {
+            let running = scope.spawn(|| {
+                let steps = [MacroStep::Send(KeySequence {
+                    chords: vec![ctrl(Key::KeyK), ctrl(Key::KeyS)],
+                    delay_ms: 60_000,
+                })];
+                run_steps(&steps, &sender, &spawner, &clipboard, &|_| true, &cancel)
+            });
+            while recorder.events().len() < sent + 4 {
+                thread::sleep(CANCEL_POLL);
+            }
+            cancel.store(true, Ordering::Relaxed);
+            running.join().unwrap().unwrap();
+        });
+        assert_eq!(recorder.events().len(), sent + 4);
+    }
+}
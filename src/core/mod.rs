@@ -1,4 +1,9 @@
+pub mod app_config;
 pub mod context;
+pub mod executor;
 pub mod extensions;
+pub mod history;
+pub mod macros;
 pub mod registry;
 pub mod search;
+pub mod snippets;
@@ -0,0 +1,359 @@
+// Shortcut conflict detection across loaded extensions
+// Design/Overview.md: current_context + action_context + no collision -> action is valid
+
+use std::{cmp::Reverse, collections::HashMap, fmt};
+
+use crate::{
+    core::registry::registry::{MasterRegistry, UnitAction},
+    models::{
+        action::{ActionId, ActionName, AppName, ApplicationID, FocusState, Priority},
+        hotkey::KeySequence,
+    },
+};
+
+// Which of two bindings on the same chord receives the keys.
+// The higher priority layer wins, on a tie the focused app wins over the background.
+pub fn binding_rank(priority: Priority, focus_state: FocusState) -> (Priority, bool) {
+    (priority, focus_state == FocusState::Focused)
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct BindingRef {
+    pub app_id: ApplicationID,
+    pub app_name: AppName,
+    pub action_id: ActionId,
+    pub action_name: ActionName,
+    pub focus_state: FocusState,
+    pub priority: Priority,
+    pub shortcut: KeySequence,
+}
+
+impl BindingRef {
+    // None for an action without a chord of its own
+    pub fn of(action: &UnitAction) -> Option<BindingRef> {
+        Some(BindingRef {
+            app_id: action.app_id.clone(),
+            app_name: action.app_name.clone(),
+            action_id: action.action_id.clone(),
+            action_name: action.action_name.clone(),
+            focus_state: action.focus_state,
+            priority: action.priority,
+            shortcut: action.kind.shortcut()?.clone(),
+        })
+    }
+
+    // Focused actions only fire while their own app is focused, so two of them only
+    // collide inside the same app. Background and global actions are always live.
+    fn overlaps(&self, other: &BindingRef) -> bool {
+        match (self.focus_state, other.focus_state) {
+            (FocusState::Focused, FocusState::Focused) => self.app_id == other.app_id,
+            _ => true,
+        }
+    }
+}
+
+impl fmt::Display for BindingRef {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} / {} ({:?}, {:?})",
+            self.app_name, self.action_name, self.priority, self.focus_state
+        )
+    }
+}
+
+// `shortcut` is the one of the winner. A shadowed sequence can be longer, when
+// the winner takes its first chords
+#[derive(Debug, Clone)]
+pub struct Conflict {
+    pub shortcut: KeySequence,
+    pub winner: BindingRef,
+    pub shadowed: Vec<BindingRef>,
+}
+
+#[derive(Debug, Default)]
+pub struct ConflictReport {
+    pub conflicts: Vec<Conflict>,
+}
+
+impl ConflictReport {
+    pub fn is_empty(&self) -> bool {
+        self.conflicts.is_empty()
+    }
+
+    fn add(&mut self, winner: &BindingRef, shadowed: &BindingRef) {
+        let conflict = self
+            .conflicts
+            .iter_mut()
+            .find(|c| c.winner == *winner && c.shortcut == winner.shortcut);
+        match conflict {
+            Some(conflict) => conflict.shadowed.push(shadowed.clone()),
+            None => self.conflicts.push(Conflict {
+                shortcut: winner.shortcut.clone(),
+                winner: winner.clone(),
+                shadowed: vec![shadowed.clone()],
+            }),
+        }
+    }
+
+    pub fn is_shadowed(&self, app_id: &str, action_id: &str) -> bool {
+        self.conflicts.iter().any(|c| {
+            c.shadowed
+                .iter()
+                .any(|b| b.app_id == app_id && b.action_id == action_id)
+        })
+    }
+}
+
+impl fmt::Display for ConflictReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.conflicts.is_empty() {
+            return writeln!(f, "No shortcut conflicts found");
+        }
+
+        for conflict in &self.conflicts {
+            writeln!(f, "{}", conflict.shortcut)?;
+            writeln!(f, "  wins:     {}", conflict.winner)?;
+            for shadowed in &conflict.shadowed {
+                if shadowed.shortcut == conflict.shortcut {
+                    writeln!(f, "  shadowed: {shadowed}")?;
+                } else {
+                    writeln!(f, "  shadowed: {shadowed} on {}", shadowed.shortcut)?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+// Every binding whose chord is taken by a higher ranked binding in an overlapping
+// context is shadowed by the best of those. A sequence is also shadowed by an
+// overlapping binding on its first chords, whatever their rank: that one runs as
+// soon as they are pressed, the rest of the sequence never comes.
+// Returns (winner, shadowed) indices into `bindings`, earlier bindings win exact ties
+pub fn shadowing(bindings: &[BindingRef]) -> Vec<(usize, usize)> {
+    let rank = |idx: usize| {
+        let b = &bindings[idx];
+        (binding_rank(b.priority, b.focus_state), Reverse(idx))
+    };
+    let best = |idx: usize, takes_keys: &dyn Fn(&BindingRef) -> bool| {
+        (0..bindings.len())
+            .filter(|&other| other != idx && takes_keys(&bindings[other]))
+            .filter(|&other| bindings[other].overlaps(&bindings[idx]))
+            .max_by_key(|&other| rank(other))
+    };
+
+    let mut pairs = vec![];
+    for (idx, binding) in bindings.iter().enumerate() {
+        let same_chord = |other: &BindingRef| other.shortcut == binding.shortcut;
+        if let Some(winner) = best(idx, &same_chord).filter(|&w| rank(w) > rank(idx)) {
+            pairs.push((winner, idx));
+        }
+    }
+    for (idx, long) in bindings.iter().enumerate() {
+        let prefix = |short: &BindingRef| short.shortcut.is_prefix_of(&long.shortcut);
+        if let Some(winner) = best(idx, &prefix) {
+            pairs.push((winner, idx));
+        }
+    }
+    pairs
+}
+
+// The shadowing of every action of the registry, grouped by chord
+pub fn analyse(registry: &MasterRegistry) -> ConflictReport {
+    // Sort so the report and tie breaking do not depend on HashMap order
+    let mut app_ids: Vec<&ApplicationID> = registry.application_registry.keys().collect();
+    app_ids.sort();
+
+    let mut by_shortcut: Vec<(KeySequence, Vec<BindingRef>)> = vec![];
+    let mut shortcut_idx: HashMap<KeySequence, usize> = HashMap::new();
+    for app_id in app_ids {
+        let app = &registry.application_registry[app_id];
+        let mut actions: Vec<_> = app.actions().iter().collect();
+        actions.sort_by_key(|(action_id, _)| *action_id);
+
+        for (action_id, action) in actions {
+            let Some(shortcut) = action.kind.shortcut() else {
+                continue;
+            };
+            let binding = BindingRef {
+                app_id: app_id.clone(),
+                app_name: app.application_name().clone(),
+                action_id: action_id.clone(),
+                action_name: action.name.clone(),
+                focus_state: action.focus_state,
+                priority: action.priority,
+                shortcut: shortcut.clone(),
+            };
+            let idx = *shortcut_idx.entry(shortcut.clone()).or_insert_with(|| {
+                by_shortcut.push((shortcut.clone(), vec![]));
+                by_shortcut.len() - 1
+            });
+            by_shortcut[idx].1.push(binding);
+        }
+    }
+
+    let all: Vec<BindingRef> = by_shortcut.into_iter().flat_map(|(_, b)| b).collect();
+    let mut report = ConflictReport::default();
+    for (winner, shadowed) in shadowing(&all) {
+        report.add(&all[winner], &all[shadowed]);
+    }
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        core::registry::registry::Application,
+        models::{action::Os, config::Config},
+    };
+
+    fn app(id: &str, process: &str, actions: &str) -> (ApplicationID, Application) {
+        let content = format!(
+            r#"
+version = 2
+
+[app]
+id = "{id}"
+name = "{id}"
+default_focus_state = "focused"
+default_priority = "application"
+
+[app.application_os_name]
+windows = "{process}"
+
+{actions}
+"#
+        );
+        let config: Config = toml::from_str(&content).unwrap();
+        (id.into(), Application::new(&config, &Os::Windows))
+    }
+
+    fn registry(apps: Vec<(ApplicationID, Application)>) -> MasterRegistry {
+        let mut registry = MasterRegistry::default();
+        registry.application_registry.extend(apps);
+        registry
+    }
+
+    #[test]
+    fn focused_actions_in_different_apps_do_not_conflict() {
+        let new_tab = r#"
+[actions.new_tab]
+name = "New tab"
+cmd.windows = { mods = ["ctrl"], key = "t" }
+"#;
+        let registry = registry(vec![
+            app("chrome", "chrome.exe", new_tab),
+            app("explorer", "explorer.exe", new_tab),
+        ]);
+
+        assert!(analyse(&registry).is_empty());
+    }
+
+    #[test]
+    fn same_app_conflict_is_resolved_by_priority() {
+        let actions = r#"
+[actions.new_tab]
+name = "New tab"
+cmd.windows = { mods = ["ctrl"], key = "t" }
+
+[actions.vimium_new_tab]
+name = "Vimium new tab"
+priority = "application_extensions"
+cmd.windows = { mods = ["ctrl"], key = "t" }
+"#;
+        let registry = registry(vec![app("chrome", "chrome.exe", actions)]);
+
+        let report = analyse(&registry);
+        assert_eq!(report.conflicts.len(), 1);
+        assert_eq!(report.conflicts[0].winner.action_name, "New tab");
+        assert_eq!(
+            report.conflicts[0].shadowed[0].action_name,
+            "Vimium new tab"
+        );
+
+        let shadowed = &report.conflicts[0].shadowed[0];
+        assert!(report.is_shadowed(&shadowed.app_id, &shadowed.action_id));
+        assert!(report
+            .to_string()
+            .contains("shadowed: chrome / Vimium new tab"));
+    }
+
+    #[test]
+    fn global_actions_overlap_every_app() {
+        let chrome = r#"
+[actions.new_tab]
+name = "New tab"
+cmd.windows = { mods = ["ctrl"], key = "t" }
+"#;
+        let explorer = r#"
+[actions.new_tab]
+name = "New tab"
+cmd.windows = { mods = ["ctrl"], key = "t" }
+"#;
+        let launcher = r#"
+[actions.terminal]
+name = "Open terminal"
+focus_state = "global"
+priority = "os_global"
+cmd.windows = { mods = ["ctrl"], key = "t" }
+"#;
+        let registry = registry(vec![
+            app("chrome", "chrome.exe", chrome),
+            app("explorer", "explorer.exe", explorer),
+            app("launcher", "launcher.exe", launcher),
+        ]);
+
+        let report = analyse(&registry);
+        assert_eq!(report.conflicts.len(), 1);
+        assert_eq!(report.conflicts[0].winner.action_name, "Open terminal");
+        assert_eq!(report.conflicts[0].shadowed.len(), 2);
+    }
+
+    #[test]
+    fn chord_shadows_sequences_starting_with_it() {
+        let editor = r#"
+[actions.save_all]
+name = "Save all"
+priority = "user_overrides"
+cmd.windows = [{ mods = ["ctrl"], key = "k" }, { mods = ["ctrl"], key = "s" }]
+
+[actions.format]
+name = "Format"
+cmd.windows = [{ mods = ["ctrl"], key = "s" }, { mods = ["ctrl"], key = "k" }]
+"#;
+        let launcher = r#"
+[actions.search]
+name = "Search"
+focus_state = "global"
+cmd.windows = { mods = ["ctrl"], key = "k" }
+"#;
+        let report = analyse(&registry(vec![
+            app("editor", "code.exe", editor),
+            app("launcher", "launcher.exe", launcher),
+        ]));
+        assert_eq!(report.conflicts.len(), 1);
+        let conflict = &report.conflicts[0];
+        assert_eq!(conflict.shortcut.to_string(), "Ctrl+K");
+        assert_eq!(conflict.winner.action_name, "Search");
+        assert_eq!(conflict.shadowed.len(), 1);
+        assert!(report.is_shadowed("editor", "save_all"));
+        assert!(!report.is_shadowed("editor", "format"));
+        assert!(report
+            .to_string()
+            .contains("shadowed: editor / Save all (UserOverrides, Focused) on Ctrl+K Ctrl+S"));
+
+        // Focused in an other app, the sequence can still be typed in the editor
+        let other_app = r#"
+[actions.search]
+name = "Search"
+cmd.windows = { mods = ["ctrl"], key = "k" }
+"#;
+        let report = analyse(&registry(vec![
+            app("editor", "code.exe", editor),
+            app("browser", "chrome.exe", other_app),
+        ]));
+        assert!(report.is_empty());
+    }
+}
@@ -1 +1,3 @@
+pub mod conflicts;
+#[allow(clippy::module_inception)]
 pub mod registry;
@@ -2,25 +2,29 @@
 
 use std::{
     collections::{HashMap, HashSet},
-    fmt::Error,
-    fs,
-    ops::Add,
-    path::Path,
-    rc::Rc,
-    task::Context,
+    path::{Path, PathBuf},
 };
 
-use log::{error, info, warn};
+use log::{debug, info, warn};
 
 use crate::{
-    core::extensions::extensions::load_config,
+    core::{
+        extensions::{
+            extensions::load_config,
+            validation::{unresolved_runs, validate, RunStep},
+            watcher::normalize,
+        },
+        registry::conflicts::{self, BindingRef},
+        snippets,
+    },
     models::{
         action::{
-            self, Action, ActionId, ActionName, AppName, AppProcessName, ApplicationID,
-            ContextRoot, FocusState, Os, Priority,
+            Action, ActionId, ActionKind, ActionName, AppName, AppProcessName, ApplicationID,
+            ContextRoot, FocusState, MacroStep, Os, Priority, Spawn,
         },
-        config::{CmdByOs, Config, KeyChord, Modifier},
-        hotkey::{HotkeyModifiers, Key, KeyboardShortcut},
+        config::{Action as ConfigAction, Config, Step},
+        extension_error::ExtensionError,
+        hotkey::KeySequence,
     },
     platform::platform_interface::RawWindowHandleExt,
 };
@@ -31,70 +35,254 @@ pub struct MasterRegistry {
     // 2 way: can be lazy generated when the user pulls up the palette or pregenerated.
     pub application_registry: HashMap<ApplicationID, Application>,
     pub application_process_name_id: HashMap<AppProcessName, ApplicationID>,
+    // Extension files with their precedence level, lowest precedence first. A later
+    // file overrides an earlier one with the same app id from an other level
+    files: Vec<(PathBuf, usize)>,
+    // Last good version of every file, kept while the file is broken
+    loaded: HashMap<PathBuf, Application>,
+    // Problems of each file, shown in the palette until fixed
+    load_errors: HashMap<PathBuf, Vec<ExtensionError>>,
+    // Deprecations and actions missing for this OS of each file, it still loads
+    load_warnings: HashMap<PathBuf, Vec<ExtensionError>>,
+    // Files reusing the app id of an other file on the same level, left out
+    duplicates: HashMap<PathBuf, ExtensionError>,
+    // Run steps of each file, with the last good version like `loaded`
+    runs: HashMap<PathBuf, Vec<RunStep>>,
+    // Run steps of the apps in use that point at no loaded action
+    unresolved_runs: HashMap<PathBuf, Vec<ExtensionError>>,
 }
 
 impl MasterRegistry {
-    pub fn build(extensions_folder: &Path, current_os: Os) -> MasterRegistry {
+    // Files given one by one, ie to validate them, so all on the same level
+    pub fn from_files(paths: &[PathBuf], current_os: Os) -> MasterRegistry {
+        let files: Vec<(PathBuf, usize)> = paths.iter().map(|p| (p.clone(), 0)).collect();
+        MasterRegistry::from_sources(&files, current_os)
+    }
+
+    // The files from ExtensionSources::resolve, lowest precedence first
+    pub fn from_sources(files: &[(PathBuf, usize)], current_os: Os) -> MasterRegistry {
         let mut master_registry = MasterRegistry::default();
+        let paths: Vec<PathBuf> = files.iter().map(|(f, _)| f.clone()).collect();
+        master_registry.reload(files, &paths, current_os);
+        master_registry
+    }
+
+    // `files` is the new set of extension files, `changed` the ones edited on disk.
+    // A removed file drops its app, a broken one keeps the last good version
+    pub fn reload(&mut self, files: &[(PathBuf, usize)], changed: &[PathBuf], current_os: Os) {
+        let files: Vec<(PathBuf, usize)> = files
+            .iter()
+            .map(|(f, level)| (normalize(f), *level))
+            .collect();
+        let changed: Vec<PathBuf> = changed.iter().map(|f| normalize(f)).collect();
+
+        let listed = |f: &PathBuf| files.iter().any(|(file, _)| file == f);
+        self.loaded.retain(|f, _| listed(f));
+        self.load_errors.retain(|f, _| listed(f));
+        self.load_warnings.retain(|f, _| listed(f));
+        self.runs.retain(|f, _| listed(f));
+        for (file, _) in &files {
+            let known = self.loaded.contains_key(file) || self.load_errors.contains_key(file);
+            if changed.contains(file) || !known {
+                self.load_file(file, current_os);
+            }
+        }
+
+        self.files = files;
+        self.rebuild();
+    }
 
-        // Use a match on read_dir to handle the folder being missing/inaccessible
-        match fs::read_dir(extensions_folder) {
-            Ok(entries) => {
-                for (idx, entry) in entries.enumerate() {
-                    // Handle individual directory entry errors
-                    let entry = match entry {
-                        Ok(e) => e,
-                        Err(err) => {
-                            warn!("Failed to read directory entry {}: {}", idx, err);
-                            continue;
-                        }
-                    };
-
-                    let path = entry.path();
-
-                    // Skip non-toml files
-                    if path.extension().and_then(|s| s.to_str()) != Some("toml") {
-                        continue;
-                    }
-
-                    // Load and build application
-                    match load_config(&path).and_then(|c| Application::new(&c, &current_os)) {
-                        Ok(app) => {
-                            info!(
-                                "Successfully loaded extension: {:?}",
-                                path.file_name().unwrap()
-                            );
-                            master_registry
-                                .application_registry
-                                .insert(idx as u32, app.clone());
-                            master_registry
-                                .application_process_name_id
-                                .insert(app.application_process_name.clone(), idx as u32);
-                        }
-                        Err(err) => {
-                            error!("Failed to load extension at {:?}: {}", path, err);
-                        }
-                    }
-                }
+    // Actions with problems are left out, a file that cannot be read or parsed
+    // leaves the last good version in place
+    fn load_file(&mut self, path: &Path, current_os: Os) {
+        let (app, errors, warnings) = match load_config(path) {
+            Ok(file) => {
+                let validation = validate(&file, current_os);
+                self.runs.insert(path.to_path_buf(), validation.runs);
+                let app = Application::new(&file.config, &current_os);
+                let mut warnings = file.deprecations;
+                warnings.extend(validation.warnings);
+                (
+                    Some((file.config.version, app)),
+                    validation.errors,
+                    warnings,
+                )
             }
-            Err(e) => error!(
-                "Could not access extensions directory at {:?}: {}",
-                extensions_folder, e
-            ),
+            Err(err) => (None, vec![err], vec![]),
         };
 
-        master_registry
+        for err in &errors {
+            warn!("Extension problem: {err}");
+        }
+        for warning in &warnings {
+            warn!("Extension warning: {warning}");
+        }
+        if warnings.is_empty() {
+            self.load_warnings.remove(path);
+        } else {
+            self.load_warnings.insert(path.to_path_buf(), warnings);
+        }
+        if let Some((version, app)) = app {
+            info!(
+                "Successfully loaded extension {} (version {version}): {:?}",
+                app.application_id,
+                path.file_name().unwrap_or_default()
+            );
+            self.loaded.insert(path.to_path_buf(), app);
+        }
+        if errors.is_empty() {
+            self.load_errors.remove(path);
+        } else {
+            self.load_errors.insert(path.to_path_buf(), errors);
+        }
+    }
+
+    // "app/action" ids must point at exactly one action, so of two files with
+    // the same app id only the one with the higher precedence is used. On the
+    // same level neither has precedence, the later one is reported and left out
+    fn rebuild(&mut self) {
+        self.application_registry.clear();
+        self.application_process_name_id.clear();
+        self.duplicates.clear();
+
+        let mut owners: HashMap<&ApplicationID, &PathBuf> = HashMap::new();
+        let mut on_level: HashMap<(&ApplicationID, usize), &PathBuf> = HashMap::new();
+        for (file, level) in &self.files {
+            let Some(app) = self.loaded.get(file) else {
+                continue;
+            };
+            if let Some(other) = on_level.get(&(&app.application_id, *level)) {
+                let reason = format!(
+                    "app id `{}` is already used by {}",
+                    app.application_id,
+                    other.display()
+                );
+                warn!("Extension problem: {}: {reason}", file.display());
+                self.duplicates
+                    .insert(file.clone(), ExtensionError::file(file.clone(), reason));
+                continue;
+            }
+            on_level.insert((&app.application_id, *level), file);
+
+            if let Some(previous) = owners.insert(&app.application_id, file) {
+                info!(
+                    "{} from {file:?} overrides the one from {previous:?}",
+                    app.application_id
+                );
+            }
+            self.application_registry
+                .insert(app.application_id.clone(), app.clone());
+        }
+
+        // Same order, so a process claimed by two apps goes to the later one
+        for (file, _) in &self.files {
+            let Some(app) = self.loaded.get(file) else {
+                continue;
+            };
+            let Some(process_name) = &app.application_process_name else {
+                continue;
+            };
+            if owners.get(&app.application_id) == Some(&file) {
+                self.application_process_name_id
+                    .insert(process_name.clone(), app.application_id.clone());
+            }
+        }
+
+        // A run may point into any file, so only now that all of them are in
+        self.unresolved_runs = self
+            .files
+            .iter()
+            .filter(|(file, _)| {
+                self.loaded
+                    .get(file)
+                    .is_some_and(|app| self.source_of(&app.application_id) == Some(file.as_path()))
+            })
+            .filter_map(|(file, _)| {
+                let unresolved = unresolved_runs(self.runs.get(file)?, self);
+                (!unresolved.is_empty()).then(|| (file.clone(), unresolved))
+            })
+            .collect();
+    }
+
+    // File the app in use was loaded from, the one with the highest precedence
+    pub fn source_of(&self, app_id: &str) -> Option<&Path> {
+        self.files
+            .iter()
+            .rev()
+            .map(|(file, _)| file)
+            .filter(|file| !self.duplicates.contains_key(*file))
+            .find(|file| {
+                self.loaded
+                    .get(*file)
+                    .is_some_and(|app| app.application_id == app_id)
+            })
+            .map(PathBuf::as_path)
+    }
+
+    // Every problem of the loaded extension files, in precedence then file order
+    pub fn load_errors(&self) -> Vec<&ExtensionError> {
+        self.files
+            .iter()
+            .flat_map(|(file, _)| {
+                self.load_errors
+                    .get(file)
+                    .into_iter()
+                    .flatten()
+                    .chain(self.duplicates.get(file))
+                    .chain(self.unresolved_runs.get(file).into_iter().flatten())
+            })
+            .collect()
+    }
+
+    // Deprecated schema and actions missing for this OS, same order
+    pub fn load_warnings(&self) -> Vec<&ExtensionError> {
+        self.files
+            .iter()
+            .filter_map(|(file, _)| self.load_warnings.get(file))
+            .flatten()
+            .collect()
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct UnitAction {
     // This struct will be use for search and generating the UI
+    pub app_id: ApplicationID,
     pub app_name: AppName,
     pub action_id: ActionId,
     pub action_name: ActionName,
     pub focus_state: FocusState,
-    pub keyboard_shortcut: KeyboardShortcut,
+    pub kind: ActionKind,
+    pub shortcut: Option<String>, // Rendered chords of a shortcut action, searched as typed
+    pub aliases: Vec<String>,
+    pub description: Option<String>,
+    pub keywords: Vec<String>,
+    pub category: Option<String>,
+    pub priority: Priority,
+}
+
+impl UnitAction {
+    fn new(app: &Application, action_id: &ActionId, action: &Action) -> Self {
+        UnitAction {
+            app_id: app.application_id.clone(),
+            app_name: app.application_name.clone(),
+            action_id: action_id.clone(),
+            action_name: action.name.clone(),
+            focus_state: action.focus_state,
+            kind: action.kind.clone(),
+            shortcut: action.kind.shortcut().map(ToString::to_string),
+            aliases: action.aliases.clone(),
+            description: action.description.clone(),
+            keywords: action.keywords.clone(),
+            category: action.category.clone(),
+            priority: action.priority,
+        }
+    }
+
+    // Same on every run and machine, ie "chrome/new_tab"
+    pub fn id(&self) -> String {
+        format!("{}/{}", self.app_id, self.action_id)
+    }
 }
 
 impl MasterRegistry {
@@ -108,170 +296,576 @@ impl MasterRegistry {
                 continue;
             };
 
-            let Some(&app_id) = self.application_process_name_id.get(&process_name) else {
+            let Some(app_id) = self.application_process_name_id.get(&process_name) else {
                 continue;
             };
 
-            let Some(app) = self.application_registry.get(&app_id) else {
+            let Some(app) = self.application_registry.get(app_id) else {
                 continue;
             };
 
-            for (&action_id, action) in app
+            for (action_id, action) in app
                 .application_registry
                 .iter()
                 .filter(|(_, a)| a.focus_state == FocusState::Background)
             {
-                all_actions.push(UnitAction {
-                    app_name: app.application_name.clone(),
-                    action_id: action_id,
-                    action_name: action.name.clone(),
-                    focus_state: FocusState::Background,
-                    keyboard_shortcut: action.keyboard_shortcut,
-                });
+                all_actions.push(UnitAction::new(app, action_id, action));
             }
         }
 
         // Extract Focused Actions
         'add_focused_actions: {
-            dbg!("app_id");
-
             let Some(active) = context.get_active() else {
                 break 'add_focused_actions;
             };
-            dbg!(&active);
 
             let Some(process_name) = active.get_app_process_name() else {
                 break 'add_focused_actions;
             };
-            dbg!(&process_name);
-            dbg!(&self.application_process_name_id);
 
             let Some(app_id) = self.application_process_name_id.get(&process_name) else {
                 break 'add_focused_actions;
             };
 
-            dbg!(&app_id);
+            debug!("Focused window is {process_name}, app {app_id}");
 
-            let Some(app) = self.application_registry.get(&app_id) else {
+            let Some(app) = self.application_registry.get(app_id) else {
                 break 'add_focused_actions;
             };
 
-            for (&action_id, action) in app
+            for (action_id, action) in app
                 .application_registry
                 .iter()
                 .filter(|(_, a)| a.focus_state == FocusState::Focused)
             {
-                all_actions.push(UnitAction {
-                    app_name: app.application_name.clone(),
-                    action_id: action_id,
-                    action_name: action.name.clone(),
-                    focus_state: FocusState::Background,
-                    keyboard_shortcut: action.keyboard_shortcut,
-                });
+                all_actions.push(UnitAction::new(app, action_id, action));
             }
         }
 
-        all_actions
+        // Global actions run whatever has focus, ie a launcher
+        let mut app_ids: Vec<&ApplicationID> = self.application_registry.keys().collect();
+        app_ids.sort();
+        for app_id in app_ids {
+            let app = &self.application_registry[app_id];
+            for (action_id, action) in app
+                .application_registry
+                .iter()
+                .filter(|(_, a)| a.focus_state == FocusState::Global)
+            {
+                all_actions.push(UnitAction::new(app, action_id, action));
+            }
+        }
+
+        resolve_bindings(all_actions)
+    }
+
+    // Look up an id from UnitAction::id, ie "chrome/new_tab"
+    pub fn find_action(&self, id: &str) -> Option<UnitAction> {
+        let (app_id, action_id) = id.split_once('/')?;
+        let app = self.application_registry.get(app_id)?;
+        let (action_id, action) = app.application_registry.get_key_value(action_id)?;
+        Some(UnitAction::new(app, action_id, action))
     }
 }
 
+// When two sources claim the same chord only one of them receives the keys, the
+// shadowed one is hidden from the palette. So is a sequence whose first chords
+// are bound on their own. Same rules as the conflict report, see
+// `conflicts::shadowing`. The result is ordered by priority, highest first.
+pub fn resolve_bindings(actions: Vec<UnitAction>) -> Vec<UnitAction> {
+    // Indices into `actions` of the ones with a chord
+    let (bound, bindings): (Vec<usize>, Vec<BindingRef>) = actions
+        .iter()
+        .enumerate()
+        .filter_map(|(idx, action)| Some((idx, BindingRef::of(action)?)))
+        .unzip();
+
+    let mut hidden = HashSet::new();
+    for (winner, shadowed) in conflicts::shadowing(&bindings) {
+        if hidden.insert(bound[shadowed]) {
+            info!(
+                "{} is hidden, {} takes the keys of {}",
+                bindings[shadowed], bindings[winner], bindings[shadowed].shortcut
+            );
+        }
+    }
+
+    let mut resolved: Vec<UnitAction> = actions
+        .into_iter()
+        .enumerate()
+        .filter(|(idx, _)| !hidden.contains(idx))
+        .map(|(_, action)| action)
+        .collect();
+
+    // Stable, so the registry order is kept within a layer
+    resolved.sort_by_key(|a| std::cmp::Reverse(a.priority));
+    resolved
+}
+
 #[derive(Debug, Clone)] // Debug is useful for printing
 pub struct Application {
+    application_id: String, // `app.id` of the extension file
     application_name: AppName,
-    application_process_name: AppProcessName,
+    application_process_name: Option<AppProcessName>, // None for an app of global actions only
     application_registry: HashMap<ActionId, Action>,
 }
 
 impl Application {
-    pub fn new(app_config: &Config, current_os: &Os) -> Result<Application, String> {
-        let application_os_name = match current_os {
-            Os::Windows => app_config
-                .app
-                .application_os_name
-                .windows
-                .clone()
-                .ok_or("No OS app name"),
-            Os::Mac => app_config
-                .app
-                .application_os_name
-                .macos
-                .clone()
-                .ok_or("No OS app name"),
-            Os::Linux => app_config
+    // Actions without a single kind or a focus state for this OS are left out,
+    // core::extensions::validation reports them
+    pub fn new(app_config: &Config, current_os: &Os) -> Application {
+        let mut application_registry: HashMap<ActionId, Action> = HashMap::new();
+
+        for (action_key, config_action) in app_config.actions.iter() {
+            let Some(kind) = action_kind(config_action, *current_os, &app_config.app.id) else {
+                continue;
+            };
+            let Some(focus_state) = config_action
+                .focus_state
+                .or(app_config.app.default_focus_state)
+            else {
+                continue;
+            };
+
+            let app_action: Action = Action {
+                name: config_action.name.clone(),
+                kind,
+                focus_state,
+                priority: config_action
+                    .priority
+                    .unwrap_or(app_config.app.default_priority)
+                    .into(),
+                aliases: config_action.aliases.clone(),
+                description: config_action.description.clone(),
+                keywords: config_action.keywords.clone(),
+                category: config_action.category.clone(),
+            };
+            application_registry.insert(action_key.get_ref().clone(), app_action);
+        }
+
+        Application {
+            application_id: app_config.app.id.clone(),
+            application_name: app_config.app.name.clone(),
+            application_process_name: app_config
                 .app
                 .application_os_name
-                .linux
-                .clone()
-                .ok_or("No OS app name"),
-        }?;
+                .for_os(*current_os)
+                .cloned(),
+            application_registry,
+        }
+    }
 
-        let mut application_registry: HashMap<ActionId, Action> = HashMap::new();
+    pub fn application_name(&self) -> &AppName {
+        &self.application_name
+    }
 
-        fn extract_os_binding<'a>(
-            action: &'a CmdByOs,
-            _current_os: &Os,
-        ) -> Result<&'a KeyChord, String> {
-            match _current_os {
-                Os::Windows => action
-                    .windows
-                    .as_ref()
-                    .ok_or_else(|| "No Windows action".to_string()),
-                Os::Mac => action
-                    .macos
-                    .as_ref()
-                    .ok_or_else(|| "No Mac action".to_string()),
-                Os::Linux => action
-                    .linux
-                    .as_ref()
-                    .ok_or_else(|| "No Linux action".to_string()),
-            }
+    pub fn application_process_name(&self) -> Option<&AppProcessName> {
+        self.application_process_name.as_ref()
+    }
+
+    pub fn actions(&self) -> &HashMap<ActionId, Action> {
+        &self.application_registry
+    }
+}
+
+// The one of cmd, steps, launch, open, shell and type_text set for `os`. None
+// when there is none, more than one, an empty one, an empty send step or a
+// broken template
+fn action_kind(action: &ConfigAction, os: Os, app_id: &str) -> Option<ActionKind> {
+    let mut kinds = [
+        action
+            .cmd
+            .for_os(os)
+            .map(|binding| ActionKind::Shortcut(KeySequence::from(binding))),
+        action.steps.for_os(os).map(|steps| {
+            ActionKind::Macro(steps.iter().map(|step| macro_step(step, app_id)).collect())
+        }),
+        action
+            .launch
+            .for_os(os)
+            .map(|launch| ActionKind::Spawn(Spawn::Launch(launch.clone()))),
+        action
+            .open
+            .for_os(os)
+            .map(|target| ActionKind::Spawn(Spawn::Open(target.clone()))),
+        action
+            .shell
+            .for_os(os)
+            .map(|line| ActionKind::Spawn(Spawn::Shell(line.clone()))),
+        action
+            .type_text
+            .for_os(os)
+            .map(|snippet| ActionKind::TypeText(snippet.clone())),
+    ]
+    .into_iter()
+    .flatten();
+
+    let kind = kinds.next()?;
+    if kinds.next().is_some() {
+        return None;
+    }
+    match &kind {
+        ActionKind::Shortcut(sequence) if sequence.chords.is_empty() => None,
+        ActionKind::Macro(steps) if steps.is_empty() => None,
+        ActionKind::Macro(steps)
+            if steps.iter().any(
+                |step| matches!(step, MacroStep::Send(sequence) if sequence.chords.is_empty()),
+            ) =>
+        {
+            None
         }
+        ActionKind::TypeText(snippet) if snippets::parse(&snippet.text).is_err() => None,
+        _ => Some(kind),
+    }
+}
 
-        let mut count: u32 = 0;
+// `run` of an action of the same file may leave out the app id
+pub fn run_target(id: &str, app_id: &str) -> String {
+    if id.contains('/') {
+        id.to_string()
+    } else {
+        format!("{app_id}/{id}")
+    }
+}
 
-        for (app_id, config_action) in app_config.actions.iter() {
-            let binding = extract_os_binding(&config_action.cmd, &current_os);
-            let binding = match binding {
-                Err(s) => {
-                    warn!("{s}");
-                    continue;
-                }
-                Ok(binding) => binding,
-            };
+fn macro_step(step: &Step, app_id: &str) -> MacroStep {
+    match step {
+        Step::Send(binding) => MacroStep::Send(KeySequence::from(binding)),
+        Step::Type(text) => MacroStep::Type(text.clone()),
+        Step::WaitMs(ms) => MacroStep::Wait(*ms),
+        Step::Focus(process) => MacroStep::Focus(process.clone()),
+        Step::Run(id) => MacroStep::Run(run_target(id, app_id)),
+        Step::Launch(launch) => MacroStep::Spawn(Spawn::Launch(launch.clone())),
+        Step::Open(target) => MacroStep::Spawn(Spawn::Open(target.clone())),
+        Step::Shell(line) => MacroStep::Spawn(Spawn::Shell(line.clone())),
+    }
+}
 
-            // let binding_ref = binding.as_ref();
-            let app_action: Action = Action {
-                name: config_action.name.clone(),
-                keyboard_shortcut: KeyboardShortcut {
-                    modifier: HotkeyModifiers {
-                        control: binding.mods.contains(&Modifier::Ctrl),
-                        shift: binding.mods.contains(&Modifier::Shift),
-                        alt: binding.mods.contains(&Modifier::Alt),
-                        win: binding.mods.contains(&Modifier::Win),
-                    },
-                    key: binding.key.into(),
-                },
-                focus_state: config_action.focus_state.clone().unwrap_or(
-                    app_config
-                        .app
-                        .default_focus_state
-                        .clone()
-                        .ok_or("No Focus state found".to_string())?,
-                ),
-            }
-            .into();
-            application_registry.insert(count, app_action);
-            count += 1;
-        }
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{
+        config::{KeyBinding, KeyChord, Modifier},
+        hotkey::Key,
+    };
+    use std::fs;
+
+    const CONFIG: &str = r#"
+version = 2
+
+[app]
+id = "chrome"
+name = "Chrome"
+default_focus_state = "focused"
+default_priority = "application"
+
+[app.application_os_name]
+windows = "chrome.exe"
+
+[actions.new_tab]
+name = "New tab"
+cmd.windows = { mods = ["ctrl"], key = "t" }
+
+[actions.vimium_new_tab]
+name = "Vimium new tab"
+priority = "application_extensions"
+cmd.windows = { mods = ["ctrl"], key = "t" }
+"#;
+
+    fn unit_actions(app: &Application) -> Vec<UnitAction> {
+        app.application_registry
+            .iter()
+            .map(|(action_id, action)| UnitAction::new(app, action_id, action))
+            .collect()
+    }
+
+    #[test]
+    fn action_priority_falls_back_to_app_default() {
+        let config: Config = toml::from_str(CONFIG).unwrap();
+        let app = Application::new(&config, &Os::Windows);
+
+        let priority_of = |name: &str| {
+            app.application_registry
+                .values()
+                .find(|a| a.name == name)
+                .map(|a| a.priority)
+        };
+        assert_eq!(priority_of("New tab"), Some(Priority::Application));
+        assert_eq!(
+            priority_of("Vimium new tab"),
+            Some(Priority::ApplicationExtensions)
+        );
+    }
+
+    #[test]
+    fn macros_keep_their_steps_and_never_conflict() {
+        let source = format!(
+            r#"{CONFIG}
+[actions.new_tab_with_text]
+name = "New tab with text"
+steps.windows = [
+  {{ run = "new_tab" }},
+  {{ wait_ms = 100 }},
+  {{ type = "hello" }},
+  {{ focus = "slack.exe" }},
+  {{ send = {{ mods = ["ctrl"], key = "t" }} }},
+]
+"#
+        );
+        let config: Config = toml::from_str(&source).unwrap();
+        let app = Application::new(&config, &Os::Windows);
+
+        let ActionKind::Macro(steps) = &app.application_registry["new_tab_with_text"].kind else {
+            panic!("not a macro");
+        };
+        assert_eq!(steps[0], MacroStep::Run("chrome/new_tab".into()));
+        assert_eq!(steps[1], MacroStep::Wait(100));
+        assert_eq!(steps[2], MacroStep::Type("hello".into()));
+        assert_eq!(steps[3], MacroStep::Focus("slack.exe".into()));
+        assert_eq!(steps[4].to_string(), "send Ctrl+T");
+
+        // Only the two Ctrl+T shortcuts compete
+        assert_eq!(resolve_bindings(unit_actions(&app)).len(), 2);
+    }
+
+    #[test]
+    fn finds_actions_by_app_id_and_action_key() {
+        let config: Config = toml::from_str(CONFIG).unwrap();
+        let mut registry = MasterRegistry::default();
+        registry
+            .application_registry
+            .insert("chrome".into(), Application::new(&config, &Os::Windows));
+
+        let found = registry.find_action("chrome/vimium_new_tab").unwrap();
+        assert_eq!(found.action_name, "Vimium new tab");
+        assert!(registry.find_action("chrome/missing").is_none());
+        assert!(registry.find_action("firefox/new_tab").is_none());
+        assert!(registry.find_action("new_tab").is_none());
+    }
+
+    #[test]
+    fn reload_keeps_the_last_good_version() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("chrome.toml");
+        let files = [(path.clone(), 0)];
+        let changed = [path.clone()];
+        fs::write(&path, CONFIG).unwrap();
+        let mut registry = MasterRegistry::from_sources(&files, Os::Windows);
+        assert!(registry.find_action("chrome/new_tab").is_some());
+
+        fs::write(&path, "version = ").unwrap();
+        registry.reload(&files, &changed, Os::Windows);
+        assert!(registry.find_action("chrome/new_tab").is_some());
+        assert_eq!(registry.load_errors().len(), 1);
+
+        fs::write(&path, CONFIG.replace("New tab", "Fresh tab")).unwrap();
+        registry.reload(&files, &changed, Os::Windows);
+        let found = registry.find_action("chrome/new_tab").unwrap();
+        assert_eq!(found.id(), "chrome/new_tab");
+        assert_eq!(found.action_name, "Fresh tab");
+        assert!(registry.load_errors().is_empty());
+
+        fs::remove_file(&path).unwrap();
+        registry.reload(&[], &changed, Os::Windows);
+        assert!(registry.application_registry.is_empty());
+        assert!(registry.application_process_name_id.is_empty());
+    }
+
+    #[test]
+    fn global_actions_need_no_window() {
+        let config: Config = toml::from_str(
+            r#"
+version = 2
+
+[app]
+id = "launcher"
+name = "Launcher"
+default_focus_state = "global"
+default_priority = "user_overrides"
+
+[actions.docs]
+name = "Rust docs"
+open.windows = "https://doc.rust-lang.org"
+
+[actions.build]
+name = "Build"
+shell.windows = "cargo build"
+launch.windows = { program = "cargo", args = ["build"] }
+"#,
+        )
+        .unwrap();
+        let mut registry = MasterRegistry::default();
+        let app = Application::new(&config, &Os::Windows);
+        assert!(app.application_process_name().is_none());
+        registry.application_registry.insert("launcher".into(), app);
+
+        let context = ContextRoot {
+            fg_context: vec![],
+            bg_context: vec![],
+        };
+        let actions = registry.get_actions(&context);
+        // Build sets two kinds and is left out
+        assert_eq!(actions.len(), 1);
+        assert!(matches!(
+            &actions[0].kind,
+            ActionKind::Spawn(Spawn::Open(url)) if url == "https://doc.rust-lang.org"
+        ));
+    }
+
+    #[test]
+    fn later_file_overrides_the_same_app_id() {
+        let dir = tempfile::tempdir().unwrap();
+        let team = dir.path().join("team.toml");
+        let personal = dir.path().join("personal.toml");
+        fs::write(&team, CONFIG).unwrap();
+        fs::write(&personal, CONFIG.replace("New tab", "My tab")).unwrap();
+
+        let files = [(team.clone(), 0), (personal.clone(), 1)];
+        let mut registry = MasterRegistry::from_sources(&files, Os::Windows);
+        assert_eq!(registry.application_registry.len(), 1);
+        let found = registry.find_action("chrome/new_tab").unwrap();
+        assert_eq!(found.action_name, "My tab");
+        assert_eq!(
+            registry.source_of("chrome"),
+            Some(normalize(&personal).as_path())
+        );
+        assert!(registry.load_errors().is_empty());
+
+        // The team version comes back once the personal one is gone
+        registry.reload(&files[..1], &[], Os::Windows);
+        let found = registry.find_action("chrome/new_tab").unwrap();
+        assert_eq!(found.action_name, "New tab");
+        assert_eq!(registry.application_process_name_id["chrome.exe"], "chrome");
+    }
+
+    #[test]
+    fn app_ids_are_unique_across_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let first = dir.path().join("first.toml");
+        let second = dir.path().join("second.toml");
+        fs::write(&first, CONFIG).unwrap();
+        fs::write(&second, CONFIG.replace("New tab", "Other tab")).unwrap();
+
+        let registry = MasterRegistry::from_files(&[first.clone(), second.clone()], Os::Windows);
+        assert_eq!(registry.application_registry.len(), 1);
+        let found = registry.find_action("chrome/new_tab").unwrap();
+        assert_eq!(found.action_name, "New tab");
+        assert_eq!(
+            registry.source_of("chrome"),
+            Some(normalize(&first).as_path())
+        );
+
+        let errors = registry.load_errors();
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].path, normalize(&second));
+        assert!(errors[0]
+            .reason
+            .contains("app id `chrome` is already used by"));
+    }
+
+    #[test]
+    fn run_steps_resolve_against_every_loaded_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let chrome = dir.path().join("chrome.toml");
+        let slack = dir.path().join("slack.toml");
+        let source = format!(
+            r#"{CONFIG}
+[actions.tab_and_mute]
+name = "Tab and mute"
+steps.windows = [{{ run = "new_tab" }}, {{ run = "slack/mute" }}]
+"#
+        );
+        fs::write(&chrome, source).unwrap();
+        fs::write(
+            &slack,
+            CONFIG.replace("chrome", "slack").replace("new_tab", "mute"),
+        )
+        .unwrap();
+
+        let files = [chrome, slack];
+        let registry = MasterRegistry::from_files(&files[..1], Os::Windows);
+        let errors = registry.load_errors();
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].action.as_deref(), Some("tab_and_mute"));
+        assert!(errors[0].reason.contains("runs `slack/mute`"));
+
+        let registry = MasterRegistry::from_files(&files, Os::Windows);
+        assert!(registry.load_errors().is_empty());
+    }
+
+    #[test]
+    fn priority_orders_by_layer() {
+        assert!(Priority::OSReserved > Priority::Application);
+        assert!(Priority::Application > Priority::DocumentOrWebApp);
+    }
+
+    #[test]
+    fn higher_priority_wins_the_chord() {
+        let config: Config = toml::from_str(CONFIG).unwrap();
+        let app = Application::new(&config, &Os::Windows);
+
+        let resolved = resolve_bindings(unit_actions(&app));
+        assert_eq!(resolved.len(), 1);
+        assert_eq!(resolved[0].action_name, "New tab");
+    }
+
+    #[test]
+    fn focused_wins_on_equal_priority() {
+        let config: Config = toml::from_str(CONFIG).unwrap();
+        let app = Application::new(&config, &Os::Windows);
+
+        let mut actions = unit_actions(&app);
+        actions.retain(|a| a.action_name == "New tab");
+        let mut background = unit_actions(&app);
+        background.retain(|a| a.action_name == "New tab");
+        background[0].focus_state = FocusState::Background;
+        background[0].app_name = "Explorer".into();
+        background.append(&mut actions);
+
+        let resolved = resolve_bindings(background);
+        assert_eq!(resolved.len(), 1);
+        assert_eq!(resolved[0].app_name, "Chrome");
+    }
 
-        Ok(Application {
-            application_name: app_config.app.name.clone().into(),
-            application_process_name: application_os_name.into(),
-            application_registry: application_registry.into(),
-        })
+    #[test]
+    fn results_are_ordered_by_priority() {
+        let mut config: Config = toml::from_str(CONFIG).unwrap();
+        config
+            .actions
+            .get_mut("vimium_new_tab")
+            .unwrap()
+            .cmd
+            .windows = Some(KeyBinding::Chord(KeyChord {
+            mods: vec![Modifier::Ctrl],
+            key: Key::KeyN,
+        }));
+        config.app.default_priority = crate::models::config::Priority::DocumentOrWebApp;
+        let app = Application::new(&config, &Os::Windows);
+
+        let resolved = resolve_bindings(unit_actions(&app));
+        assert_eq!(resolved.len(), 2);
+        assert_eq!(resolved[0].action_name, "Vimium new tab");
     }
 
-    pub fn get_action(&self, action_id: &ActionId) -> Option<&Action> {
-        self.application_registry.get(action_id)
+    #[test]
+    fn sequence_is_hidden_behind_its_first_chord() {
+        let source = format!(
+            r#"{CONFIG}
+[actions.save_all]
+name = "Save all"
+cmd.windows = [{{ mods = ["ctrl"], key = "t" }}, {{ mods = ["ctrl"], key = "s" }}]
+
+[actions.reopen]
+name = "Reopen"
+cmd.windows = [{{ mods = ["ctrl"], key = "r" }}, {{ mods = ["ctrl"], key = "s" }}]
+"#
+        );
+        let config: Config = toml::from_str(&source).unwrap();
+        let app = Application::new(&config, &Os::Windows);
+
+        let resolved = resolve_bindings(unit_actions(&app));
+        let mut names: Vec<&str> = resolved.iter().map(|a| a.action_name.as_str()).collect();
+        names.sort();
+        assert_eq!(names, ["New tab", "Reopen"]);
     }
 }
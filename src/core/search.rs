@@ -1,68 +1,621 @@
-#[derive(Default, Debug)]
+// Fuzzy scorer, based on Design/01_fuzzy_scorer.md
+// https://github.com/microsoft/vscode/blob/main/src/vs/base/common/fuzzyScorer.ts
+
+use crate::core::{
+    history::{now_secs, UsageHistory},
+    registry::registry::UnitAction,
+};
+
+#[derive(Default, Debug, Clone, PartialEq, Eq)]
 pub struct MatchResult {
-    score: i32,
-    indices: Vec<usize>, // Positions of matching chars for highlighting
+    pub score: i32,
+    pub indices: Vec<usize>, // Positions (char index) of matching chars for highlighting
+    pub field: MatchField,   // Which text of the action the indices point into
 }
 
-struct PreparedQuery {}
+// The parts of a UnitAction that can be searched
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MatchField {
+    #[default]
+    ActionName,
+    Alias(usize),   // Index into UnitAction.aliases
+    Keyword(usize), // Index into UnitAction.keywords
+    AppName,
+    Shortcut,
+    Description,
+}
 
-// struct Target
+impl MatchField {
+    // Percentage applied to the raw score. The action name is what the user usually
+    // types, the rest helps when they only remember the app or the key
+    pub fn weight(&self) -> i32 {
+        match self {
+            MatchField::ActionName => 100,
+            MatchField::Alias(_) => 90,
+            MatchField::Keyword(_) => 70,
+            MatchField::AppName => 60,
+            MatchField::Shortcut => 60,
+            MatchField::Description => 40,
+        }
+    }
+}
 
-//Entry point for external API
-// Data cleaning here
-pub fn get_score(target: &String, query: &String) -> MatchResult {
-    return do_score_contains(&target, &query);
+impl MatchResult {
+    pub fn is_match(&self) -> bool {
+        self.score > 0
+    }
 }
 
-// Filler function to get the prototype done
-fn do_score_contains(target: &String, query: &String) -> MatchResult {
-    dbg!(target);
-    dbg!(query);
+// Number of results handed back to the UI by `rank`
+pub const RANK_LIMIT: usize = 50;
 
+// The query is normalized once per keystroke and reused for every target
+#[derive(Default, Debug, Clone)]
+pub struct PreparedQuery {
+    pub original: String,
+    terms: Vec<QueryTerm>, // Whitespace separated pieces, all of them have to match
+}
+
+#[derive(Debug, Clone)]
+struct QueryTerm {
+    chars: Vec<char>,
+    lower: Vec<char>,
+}
+
+impl PreparedQuery {
+    pub fn new(query: &str) -> PreparedQuery {
+        let terms = query
+            .split_whitespace()
+            .map(|term| {
+                let chars: Vec<char> = term.chars().collect();
+                let lower = chars.iter().map(|&c| to_lower(c)).collect();
+                QueryTerm { chars, lower }
+            })
+            .collect();
+
+        PreparedQuery {
+            original: query.to_string(),
+            terms,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.terms.is_empty()
+    }
+}
+
+// Marks a cell in the match matrix that did not take part in a match
+const NO_MATCH: usize = 0;
+
+//Entry point for external API
+pub fn get_score(target: &str, query: &str) -> MatchResult {
+    score(target, &PreparedQuery::new(query))
+}
+
+// Score a single piece of text, every term has to match
+pub fn score(target: &str, query: &PreparedQuery) -> MatchResult {
     if query.is_empty() {
         return MatchResult::default();
     }
 
-    let target_lower = target.to_lowercase();
-    let query_lower = query.to_lowercase();
+    let mut result = MatchResult::default();
+    for term in &query.terms {
+        let term_result = score_term(target, term);
+        if !term_result.is_match() {
+            return MatchResult::default();
+        }
+        result.score += term_result.score;
+        result.indices.extend(term_result.indices);
+    }
 
-    let mut indices = Vec::new();
-    let mut target_chars = target_lower.char_indices().peekable();
+    result.indices.sort_unstable();
+    result.indices.dedup();
+    result
+}
+
+// Data cleaning here
+fn score_term(target: &str, term: &QueryTerm) -> MatchResult {
+    if target.is_empty() {
+        return MatchResult::default();
+    }
+
+    let target: Vec<char> = target.chars().collect();
+    let target_lower: Vec<char> = target.iter().map(|&c| to_lower(c)).collect();
 
     // 1. Filter: Sequential Character Matching
-    for q_char in query_lower.chars() {
-        let mut found = false;
-        while let Some((idx, t_char)) = target_chars.next() {
-            if t_char == q_char {
-                indices.push(idx);
-                found = true;
-                break;
+    // Cheap check before building the matrix. If any character in query isn't
+    // found in sequence, it's not a match
+    if !is_subsequence(&term.lower, &target_lower) {
+        return MatchResult::default();
+    }
+
+    // 2. Scoring
+    do_score_fuzzy(&term.chars, &term.lower, &target, &target_lower)
+}
+
+// Score one action over all of its searchable fields. Each term picks the field it
+// matches best, so "chrome tab" can hit the app name and the action name at once.
+// The reported field is the one carrying the best term, with only its indices.
+pub fn score_action(action: &UnitAction, query: &PreparedQuery) -> MatchResult {
+    if query.is_empty() {
+        return MatchResult::default();
+    }
+
+    let mut fields: Vec<(MatchField, &str)> = vec![
+        (MatchField::ActionName, &action.action_name),
+        (MatchField::AppName, &action.app_name),
+    ];
+    if let Some(shortcut) = &action.shortcut {
+        fields.push((MatchField::Shortcut, shortcut));
+    }
+    fields.extend(
+        action
+            .aliases
+            .iter()
+            .enumerate()
+            .map(|(idx, alias)| (MatchField::Alias(idx), alias.as_str())),
+    );
+    fields.extend(
+        action
+            .keywords
+            .iter()
+            .enumerate()
+            .map(|(idx, keyword)| (MatchField::Keyword(idx), keyword.as_str())),
+    );
+    if let Some(description) = &action.description {
+        fields.push((MatchField::Description, description));
+    }
+
+    let mut total = 0;
+    let mut term_results: Vec<MatchResult> = Vec::with_capacity(query.terms.len());
+    for term in &query.terms {
+        let best = fields
+            .iter()
+            .map(|&(field, text)| {
+                let mut res = score_term(text, term);
+                res.score = res.score * field.weight() / 100;
+                res.field = field;
+                res
+            })
+            .filter(|res| res.is_match())
+            .max_by(|a, b| {
+                // Prefer the earlier (more important) field on ties
+                a.score.cmp(&b.score).then(std::cmp::Ordering::Greater)
+            });
+
+        let Some(best) = best else {
+            return MatchResult::default();
+        };
+        total += best.score;
+        term_results.push(best);
+    }
+
+    let field = term_results
+        .iter()
+        .max_by(|a, b| a.score.cmp(&b.score).then(std::cmp::Ordering::Greater))
+        .map(|res| res.field)
+        .unwrap_or_default();
+
+    let mut indices: Vec<usize> = term_results
+        .into_iter()
+        .filter(|res| res.field == field)
+        .flat_map(|res| res.indices)
+        .collect();
+    indices.sort_unstable();
+    indices.dedup();
+
+    MatchResult {
+        score: total,
+        indices,
+        field,
+    }
+}
+
+// Score every action against the query and return (index into actions, result)
+// best first. Frecency is applied as a multiplier, ties keep the registry order.
+// An empty query lists the most frecent actions first.
+pub fn rank(
+    query: &PreparedQuery,
+    actions: &[UnitAction],
+    history: &UsageHistory,
+) -> Vec<(usize, MatchResult)> {
+    let now = now_secs();
+
+    let mut results: Vec<(usize, MatchResult, f64)> = if query.is_empty() {
+        actions
+            .iter()
+            .enumerate()
+            .map(|(idx, action)| {
+                let frecency = history.frecency(&action.id(), now);
+                (idx, MatchResult::default(), frecency)
+            })
+            .collect()
+    } else {
+        actions
+            .iter()
+            .enumerate()
+            .map(|(idx, action)| {
+                let mut res = score_action(action, query);
+                let boost = history.boost(&action.id(), now);
+                res.score = res.score * (100 + boost) / 100;
+                (idx, res, 0.0)
+            })
+            .filter(|(_, res, _)| res.is_match())
+            .collect()
+    };
+
+    results.sort_by(|(a_idx, a, a_frecency), (b_idx, b, b_frecency)| {
+        b.score
+            .cmp(&a.score)
+            .then(b_frecency.total_cmp(a_frecency))
+            .then(a_idx.cmp(b_idx))
+    });
+    results.truncate(RANK_LIMIT);
+    results
+        .into_iter()
+        .map(|(idx, res, _)| (idx, res))
+        .collect()
+}
+
+fn to_lower(c: char) -> char {
+    // Only take the simple 1:1 mapping so char positions stay aligned with the target
+    c.to_lowercase().next().unwrap_or(c)
+}
+
+fn is_subsequence(query_lower: &[char], target_lower: &[char]) -> bool {
+    let mut target_chars = target_lower.iter();
+    query_lower
+        .iter()
+        .all(|q_char| target_chars.any(|t_char| t_char == q_char))
+}
+
+// The scoring engine
+// Dynamic programming over a query x target matrix. For every cell we keep the
+// best score reachable and the length of the consecutive run that ends there,
+// then walk back from the bottom right corner to restore the matched positions.
+fn do_score_fuzzy(
+    query: &[char],
+    query_lower: &[char],
+    target: &[char],
+    target_lower: &[char],
+) -> MatchResult {
+    let query_len = query.len();
+    let target_len = target.len();
+
+    if target_len < query_len {
+        return MatchResult::default();
+    }
+
+    let mut scores = vec![0i32; query_len * target_len];
+    let mut matches = vec![NO_MATCH; query_len * target_len];
+
+    for query_idx in 0..query_len {
+        let query_offset = query_idx * target_len;
+
+        for target_idx in 0..target_len {
+            let current_idx = query_offset + target_idx;
+
+            let left_score = if target_idx > 0 {
+                scores[current_idx - 1]
+            } else {
+                0
+            };
+
+            let (diag_score, seq_len) = if query_idx > 0 && target_idx > 0 {
+                let diag_idx = current_idx - target_len - 1;
+                (scores[diag_idx], matches[diag_idx])
+            } else {
+                (0, 0)
+            };
+
+            // If the previous query char could not be placed before this
+            // position there is no point in scoring this one
+            let score = if query_idx > 0 && diag_score == 0 {
+                0
+            } else {
+                compute_char_score(
+                    query[query_idx],
+                    query_lower[query_idx],
+                    target,
+                    target_lower,
+                    target_idx,
+                    seq_len,
+                )
+            };
+
+            if score > 0 && diag_score + score >= left_score {
+                matches[current_idx] = seq_len + 1;
+                scores[current_idx] = diag_score + score;
+            } else {
+                matches[current_idx] = NO_MATCH;
+                scores[current_idx] = left_score;
             }
         }
-        // If any character in query isn't found in sequence, it's not a match
-        if !found {
-            return MatchResult::default();
+    }
+
+    // Restore positions, starting from the bottom right of the matrix
+    let mut indices = Vec::with_capacity(query_len);
+    let mut query_idx = query_len;
+    let mut target_idx = target_len;
+    while query_idx > 0 && target_idx > 0 {
+        let current_idx = (query_idx - 1) * target_len + (target_idx - 1);
+        if matches[current_idx] != NO_MATCH {
+            indices.push(target_idx - 1);
+            query_idx -= 1;
         }
+        target_idx -= 1;
+    }
+
+    if indices.len() != query_len {
+        return MatchResult::default();
+    }
+    indices.reverse();
+
+    MatchResult {
+        score: scores[query_len * target_len - 1],
+        indices,
+        ..Default::default()
     }
+}
 
-    // 2. Simple Scoring (Brute Force Heuristics)
-    let mut score = 100; // Base score for a match
+fn compute_char_score(
+    query_char: char,
+    query_lower_char: char,
+    target: &[char],
+    target_lower: &[char],
+    target_idx: usize,
+    seq_len: usize,
+) -> i32 {
+    if query_lower_char != target_lower[target_idx] {
+        return 0; // no match of characters
+    }
 
-    // Bonus: Character Proximity (Closer together = higher score)
-    if let (Some(&first), Some(&last)) = (indices.first(), indices.last()) {
-        let match_span = (last - first + 1) as i32;
-        score -= match_span; // Penalty for large gaps
+    // Character match bonus
+    let mut score = 1;
+
+    // Consecutive: "App" matching "Application" is better than "Aup****port"
+    if seq_len > 0 {
+        score += (seq_len.min(3) * 6) as i32 + (seq_len.saturating_sub(3) * 3) as i32;
     }
 
-    // Bonus: Start of string match
-    if indices.get(0) == Some(&0) {
-        score += 50;
+    // Same case bonus
+    if query_char == target[target_idx] {
+        score += 1;
     }
 
-    MatchResult { score, indices }
+    if target_idx == 0 {
+        // Word start: "Save File" for "sf" is highly intentional
+        score += 8;
+    } else {
+        let separator_bonus = score_separator(target[target_idx - 1]);
+        if separator_bonus > 0 {
+            // Separator: characters immediately following a /, ., _ or space
+            score += separator_bonus;
+        } else if target[target_idx].is_uppercase() && seq_len == 0 {
+            // CamelCase: "fsm" for "FiniteStateMachine". Only outside of a consecutive run
+            score += 2;
+        }
+    }
+
+    score
 }
 
-// The scorring engine
-fn do_score_fuzzy() {
-    todo!()
+fn score_separator(c: char) -> i32 {
+    match c {
+        '/' | '\\' => 5,
+        '_' | '-' | '.' | ' ' | '\'' | '"' | ':' | '+' => 4,
+        _ => 0,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{
+        action::{ActionKind, FocusState, Priority},
+        hotkey::{HotkeyModifiers, Key, KeyboardShortcut},
+    };
+
+    fn unit_action(name: &str) -> UnitAction {
+        let kind = ActionKind::Shortcut(
+            KeyboardShortcut {
+                modifier: HotkeyModifiers {
+                    control: true,
+                    ..Default::default()
+                },
+                key: Key::KeyT,
+            }
+            .into(),
+        );
+        UnitAction {
+            app_id: "chrome".into(),
+            app_name: "Chrome".into(),
+            action_id: name.to_lowercase().replace(' ', "_"),
+            action_name: name.into(),
+            focus_state: FocusState::Focused,
+            shortcut: kind.shortcut().map(ToString::to_string),
+            kind,
+            aliases: vec![],
+            description: None,
+            keywords: vec![],
+            category: None,
+            priority: Priority::Application,
+        }
+    }
+
+    #[test]
+    fn empty_query_or_target_does_not_match() {
+        assert!(!get_score("New tab", "").is_match());
+        assert!(!get_score("", "nt").is_match());
+    }
+
+    #[test]
+    fn out_of_order_query_does_not_match() {
+        // Design doc example: "gitps" never matches "Step Into"
+        assert!(get_score("Git: Push", "gitps").is_match());
+        assert!(!get_score("Step Into", "gitps").is_match());
+    }
+
+    #[test]
+    fn returns_char_indices_for_highlighting() {
+        let res = get_score("Reopen closed tab", "rct");
+        assert_eq!(res.indices, vec![0, 7, 14]);
+    }
+
+    #[test]
+    fn word_starts_beat_noise() {
+        let good = get_score("Reopen closed tab", "rct");
+        let noise = get_score("Print current tab", "rct");
+        assert!(good.score > noise.score, "{good:?} vs {noise:?}");
+    }
+
+    #[test]
+    fn consecutive_beats_scattered() {
+        let consecutive = get_score("Application", "app");
+        let scattered = get_score("Aupport", "app");
+        assert!(consecutive.score > scattered.score);
+    }
+
+    #[test]
+    fn camel_case_and_separator_bonus() {
+        let camel = get_score("FiniteStateMachine", "fsm");
+        let flat = get_score("Finitestatemachine", "fsm");
+        assert!(camel.score > flat.score);
+
+        let separated = get_score("open_file", "of");
+        let inner = get_score("proof", "of");
+        assert!(separated.score > inner.score);
+    }
+
+    #[test]
+    fn prepared_query_splits_terms() {
+        let query = PreparedQuery::new("  close   TAB ");
+        assert_eq!(query.terms.len(), 2);
+        assert_eq!(query.terms[1].lower, vec!['t', 'a', 'b']);
+        assert!(PreparedQuery::new("   ").is_empty());
+    }
+
+    #[test]
+    fn every_term_has_to_match() {
+        let query = PreparedQuery::new("tab close");
+        let res = score("Close tab", &query);
+        assert!(res.is_match());
+        assert_eq!(res.indices, vec![0, 1, 2, 3, 4, 6, 7, 8]);
+
+        assert!(!score("Close window", &query).is_match());
+    }
+
+    #[test]
+    fn rank_sorts_and_filters() {
+        let actions = vec![
+            unit_action("Print current tab"),
+            unit_action("New window"),
+            unit_action("Reopen closed tab"),
+        ];
+
+        let ranked = rank(
+            &PreparedQuery::new("rct"),
+            &actions,
+            &UsageHistory::default(),
+        );
+        let order: Vec<usize> = ranked.iter().map(|(idx, _)| *idx).collect();
+        assert_eq!(order, vec![2, 0]);
+    }
+
+    #[test]
+    fn rank_truncates() {
+        let actions: Vec<UnitAction> = (0..RANK_LIMIT + 10)
+            .map(|i| unit_action(&format!("New tab {i}")))
+            .collect();
+
+        let history = UsageHistory::default();
+        assert_eq!(
+            rank(&PreparedQuery::new("tab"), &actions, &history).len(),
+            RANK_LIMIT
+        );
+        assert_eq!(
+            rank(&PreparedQuery::new(""), &actions, &history).len(),
+            RANK_LIMIT
+        );
+    }
+
+    #[test]
+    fn matches_other_fields() {
+        let mut action = unit_action("New tab");
+        action.aliases = vec!["Open blank page".into()];
+        action.keywords = vec!["browser".into()];
+        action.description = Some("Opens a new tab next to the current one".into());
+
+        let by_alias = score_action(&action, &PreparedQuery::new("blank"));
+        assert_eq!(by_alias.field, MatchField::Alias(0));
+
+        let by_keyword = score_action(&action, &PreparedQuery::new("browser"));
+        assert_eq!(by_keyword.field, MatchField::Keyword(0));
+
+        let by_app = score_action(&action, &PreparedQuery::new("chrome"));
+        assert_eq!(by_app.field, MatchField::AppName);
+        assert_eq!(by_app.indices, vec![0, 1, 2, 3, 4, 5]);
+
+        let by_shortcut = score_action(&action, &PreparedQuery::new("ctrl+t"));
+        assert_eq!(by_shortcut.field, MatchField::Shortcut);
+
+        let by_description = score_action(&action, &PreparedQuery::new("current"));
+        assert_eq!(by_description.field, MatchField::Description);
+    }
+
+    #[test]
+    fn only_shortcut_actions_match_on_their_chords() {
+        let mut action = unit_action("Two tabs");
+        action.kind = ActionKind::Macro(vec![]);
+        action.shortcut = None;
+        assert!(!score_action(&action, &PreparedQuery::new("macro")).is_match());
+    }
+
+    #[test]
+    fn terms_can_match_different_fields() {
+        let action = unit_action("New tab");
+        let res = score_action(&action, &PreparedQuery::new("chrome new"));
+        assert!(res.is_match());
+        assert!(!score_action(&action, &PreparedQuery::new("firefox new")).is_match());
+    }
+
+    #[test]
+    fn action_name_outweighs_other_fields() {
+        let mut by_name = unit_action("Duplicate tab");
+        by_name.app_name = "Explorer".into();
+        let mut by_alias = unit_action("Copy tab");
+        by_alias.aliases = vec!["Duplicate tab".into()];
+
+        let query = PreparedQuery::new("duplicate");
+        assert!(score_action(&by_name, &query).score > score_action(&by_alias, &query).score);
+    }
+
+    #[test]
+    fn frecent_actions_are_boosted() {
+        let actions = vec![unit_action("Close tab"), unit_action("Close window")];
+
+        let mut history = UsageHistory::default();
+        history.record("chrome/close_window", now_secs());
+
+        let ranked = rank(&PreparedQuery::new("close"), &actions, &history);
+        assert_eq!(ranked[0].0, 1);
+    }
+
+    #[test]
+    fn empty_query_lists_most_frecent_first() {
+        let actions = vec![
+            unit_action("New tab"),
+            unit_action("Close tab"),
+            unit_action("Reopen closed tab"),
+        ];
+
+        let now = now_secs();
+        let mut history = UsageHistory::default();
+        history.record("chrome/close_tab", now - 60 * 60);
+        history.record("chrome/reopen_closed_tab", now - 2 * 60);
+
+        let ranked = rank(&PreparedQuery::new(""), &actions, &history);
+        let order: Vec<usize> = ranked.iter().map(|(idx, _)| *idx).collect();
+        assert_eq!(order, vec![2, 1, 0]);
+    }
 }
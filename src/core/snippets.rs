@@ -0,0 +1,280 @@
+// Snippets of type_text actions, ie canned replies. `{name}` or `{name:arg}` in
+// the text is filled in when the action runs, `{{` and `}}` are literal braces:
+//   {date}            2026-10-18, or {date:%d %B %Y} in strftime syntax
+//   {time}            14:05, or {time:%H:%M:%S}
+//   {clipboard}       text on the clipboard
+//   {selection}       text selected in the focused window, copied with Ctrl+C
+//   {window_title}    title of the focused window
+
+use std::{thread, time::Duration};
+
+use jiff::{fmt::strtime, Zoned};
+
+use crate::{
+    models::config::{Delivery, TypeText},
+    platform::{
+        clipboard::Clipboard,
+        key_sender::KeySender,
+        platform_interface::{get_foreground_context, RawWindowHandleExt},
+    },
+};
+
+// Time for the focused app to read or fill the clipboard after Ctrl+V or Ctrl+C
+const CLIPBOARD_SETTLE: Duration = Duration::from_millis(100);
+
+const DEFAULT_DATE: &str = "%Y-%m-%d";
+const DEFAULT_TIME: &str = "%H:%M";
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Placeholder {
+    Date(String), // strftime format, {time} is a date with an other default
+    Clipboard,
+    Selection,
+    WindowTitle,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Part {
+    Text(String),
+    Placeholder(Placeholder),
+}
+
+// Also used by validation, so a broken template is reported on load
+pub fn parse(template: &str) -> Result<Vec<Part>, String> {
+    let mut parts = vec![];
+    let mut text = String::new();
+    let mut chars = template.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '{' if chars.next_if_eq(&'{').is_some() => text.push('{'),
+            '}' if chars.next_if_eq(&'}').is_some() => text.push('}'),
+            '{' => {
+                let mut name = String::new();
+                let closed = loop {
+                    match chars.next() {
+                        Some('}') => break true,
+                        Some(c) => name.push(c),
+                        None => break false,
+                    }
+                };
+                if !closed {
+                    return Err(format!("{{{name} is not closed, use {{{{ for a brace"));
+                }
+                if !text.is_empty() {
+                    parts.push(Part::Text(std::mem::take(&mut text)));
+                }
+                parts.push(Part::Placeholder(placeholder(&name)?));
+            }
+            '}' => return Err("Unmatched }, use }} for a brace".to_string()),
+            _ => text.push(c),
+        }
+    }
+    if !text.is_empty() {
+        parts.push(Part::Text(text));
+    }
+    Ok(parts)
+}
+
+fn placeholder(name: &str) -> Result<Placeholder, String> {
+    let (name, arg) = match name.split_once(':') {
+        Some((name, arg)) => (name, Some(arg)),
+        None => (name, None),
+    };
+    let placeholder = match (name, arg) {
+        ("date", arg) => Placeholder::Date(arg.unwrap_or(DEFAULT_DATE).to_string()),
+        ("time", arg) => Placeholder::Date(arg.unwrap_or(DEFAULT_TIME).to_string()),
+        ("clipboard", None) => Placeholder::Clipboard,
+        ("selection", None) => Placeholder::Selection,
+        ("window_title", None) => Placeholder::WindowTitle,
+        ("clipboard" | "selection" | "window_title", Some(_)) => {
+            return Err(format!("{{{name}}} takes no argument"))
+        }
+        _ => {
+            return Err(format!(
+            "Unknown placeholder {{{name}}}, use date, time, clipboard, selection or window_title"
+        ))
+        }
+    };
+    if let Placeholder::Date(format) = &placeholder {
+        strtime::format(format, &Zoned::now())
+            .map_err(|e| format!("Bad format in {{{name}:{format}}}: {e}"))?;
+    }
+    Ok(placeholder)
+}
+
+pub fn render(
+    parts: &[Part],
+    resolve: &mut dyn FnMut(&Placeholder) -> Result<String, String>,
+) -> Result<String, String> {
+    let mut rendered = String::new();
+    for part in parts {
+        match part {
+            Part::Text(text) => rendered.push_str(text),
+            Part::Placeholder(placeholder) => rendered.push_str(&resolve(placeholder)?),
+        }
+    }
+    Ok(rendered)
+}
+
+// Fills in the template and types it into the focused window
+pub fn type_snippet(
+    snippet: &TypeText,
+    sender: &mut dyn KeySender,
+    clipboard: &mut dyn Clipboard,
+) -> Result<(), String> {
+    let parts = parse(&snippet.text)?;
+    let text = render(&parts, &mut |placeholder| match placeholder {
+        Placeholder::Date(format) => {
+            strtime::format(format, &Zoned::now()).map_err(|e| format!("Date {format}: {e}"))
+        }
+        Placeholder::Clipboard => Ok(clipboard.get_text().unwrap_or_default()),
+        Placeholder::Selection => copy_selection(sender, clipboard),
+        Placeholder::WindowTitle => Ok(get_foreground_context()
+            .and_then(|window| window.get_window_title())
+            .unwrap_or_default()),
+    })?;
+
+    match snippet.delivery {
+        Delivery::Keys => sender.type_text(&text),
+        Delivery::Paste => paste(&text, sender, clipboard),
+    }
+}
+
+// Only text is restored, a copied image or file is lost
+fn paste(
+    text: &str,
+    sender: &mut dyn KeySender,
+    clipboard: &mut dyn Clipboard,
+) -> Result<(), String> {
+    let saved = clipboard.get_text();
+    clipboard.set_text(text)?;
+    let pasted = sender.paste();
+    thread::sleep(CLIPBOARD_SETTLE);
+    if let Some(saved) = saved {
+        clipboard.set_text(&saved)?;
+    }
+    pasted
+}
+
+// The clipboard is emptied first, so no selection does not read as the old content
+fn copy_selection(
+    sender: &mut dyn KeySender,
+    clipboard: &mut dyn Clipboard,
+) -> Result<String, String> {
+    let saved = clipboard.get_text();
+    clipboard.set_text("")?;
+    let copied = sender.copy();
+    thread::sleep(CLIPBOARD_SETTLE);
+    let selection = clipboard.get_text().unwrap_or_default();
+    if let Some(saved) = saved {
+        clipboard.set_text(&saved)?;
+    }
+    copied.map(|_| selection)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        models::hotkey::{Key, Modifier},
+        platform::{
+            clipboard::MemoryClipboard,
+            key_sender::{text_events, KeyCode, KeyEvent, RecordingKeySender},
+        },
+    };
+
+    #[test]
+    fn parses_placeholders_and_escaped_braces() {
+        assert_eq!(
+            parse("Hi {clipboard}, {{ok}} {date:%d/%m}").unwrap(),
+            vec![
+                Part::Text("Hi ".into()),
+                Part::Placeholder(Placeholder::Clipboard),
+                Part::Text(", {ok} ".into()),
+                Part::Placeholder(Placeholder::Date("%d/%m".into())),
+            ]
+        );
+        assert_eq!(
+            parse("{time}").unwrap(),
+            vec![Part::Placeholder(Placeholder::Date(DEFAULT_TIME.into()))]
+        );
+
+        assert!(parse("{name}").is_err());
+        assert!(parse("{clipboard").is_err());
+        assert!(parse("a } b").is_err());
+        assert!(parse("{selection:x}").is_err());
+    }
+
+    #[test]
+    fn delivers_the_rendered_text_as_keys_or_paste() {
+        let mut clipboard = MemoryClipboard::default();
+        clipboard.set_text("ticket 42").unwrap();
+        let mut snippet = TypeText {
+            text: "Re: {clipboard}".into(),
+            delivery: Delivery::Keys,
+        };
+
+        let mut sender = RecordingKeySender::default();
+        type_snippet(&snippet, &mut sender, &mut clipboard).unwrap();
+        let typed = text_events("Re: ticket 42", &Default::default()).unwrap();
+        assert_eq!(sender.events(), typed);
+
+        snippet.delivery = Delivery::Paste;
+        let mut sender = RecordingKeySender::default();
+        type_snippet(&snippet, &mut sender, &mut clipboard).unwrap();
+        let ctrl = KeyCode::Modifier(Modifier::Control);
+        let v = KeyCode::Key(Key::KeyV);
+        assert_eq!(
+            sender.events(),
+            vec![
+                KeyEvent::Down(ctrl),
+                KeyEvent::Down(v),
+                KeyEvent::Up(v),
+                KeyEvent::Up(ctrl),
+            ]
+        );
+        assert_eq!(clipboard.get_text().as_deref(), Some("ticket 42"));
+    }
+
+    #[test]
+    fn pastes_by_default_so_any_text_works() {
+        let snippet: TypeText = toml::from_str(r#"text = "Hi {clipboard}""#).unwrap();
+        assert_eq!(snippet.delivery, Delivery::Paste);
+
+        let mut clipboard = MemoryClipboard::default();
+        clipboard.set_text("José").unwrap();
+        let mut sender = RecordingKeySender::default();
+        type_snippet(&snippet, &mut sender, &mut clipboard).unwrap();
+        assert_eq!(sender.events().len(), 4);
+        assert_eq!(clipboard.get_text().as_deref(), Some("José"));
+
+        let keys = TypeText {
+            delivery: Delivery::Keys,
+            ..snippet
+        };
+        assert!(type_snippet(&keys, &mut sender, &mut clipboard).is_err());
+    }
+
+    // Stands in for the focused app, Ctrl+C puts its selection on the clipboard
+    struct CopyingSender(MemoryClipboard);
+
+    impl KeySender for CopyingSender {
+        fn send(&mut self, _events: &[KeyEvent]) -> Result<(), String> {
+            self.0.set_text("selected words")
+        }
+    }
+
+    #[test]
+    fn selection_restores_only_a_clipboard_that_had_text() {
+        let mut clipboard = MemoryClipboard::default();
+        let mut sender = CopyingSender(clipboard.clone());
+        let selection = copy_selection(&mut sender, &mut clipboard).unwrap();
+        assert_eq!(selection, "selected words");
+        assert_eq!(clipboard.get_text().as_deref(), Some("selected words"));
+
+        clipboard.set_text("ticket 42").unwrap();
+        let selection = copy_selection(&mut sender, &mut clipboard).unwrap();
+        assert_eq!(selection, "selected words");
+        assert_eq!(clipboard.get_text().as_deref(), Some("ticket 42"));
+    }
+}
@@ -1,26 +1,151 @@
-use std::path::Path;
+use std::path::PathBuf;
 
 use env_logger::Builder;
-use log::{error, info};
+use log::{error, info, warn};
 
-use crate::core::context;
+use crate::core::app_config::load_app_config;
+use crate::core::executor::ActionExecutor;
+use crate::core::extensions::sources::ExtensionSources;
+use crate::core::extensions::watcher::watch_extensions;
+use crate::core::history::{now_secs, UsageHistory};
 use crate::core::registry::registry::UnitAction;
-use crate::core::search::{get_score, MatchResult};
-use crate::models::action::{AppProcessName, ContextRoot};
-use crate::models::hotkey::Key;
-use crate::platform::platform_interface::{get_all_context, RawWindowHandleExt};
-use crate::ui::ui_main;
-use crate::ui::ui_main::UiSignal;
+use crate::models::app_config::DirectHotkey;
+use crate::models::hotkey::KeyboardShortcut;
+use crate::platform::clipboard::SystemClipboard;
+use crate::platform::hotkey_actions::HotkeyHandle;
+use crate::platform::platform_interface::get_all_context;
+use crate::platform::register_receiver::{HotkeyId, ACTIVATION_ID};
+use crate::platform::spawner::OsSpawner;
+use crate::ui::ui_main::{PaletteBackend, UiSignal};
+use crate::ui::{cli, ui_main};
 use crate::{core::registry::registry::MasterRegistry, models::action::Os};
+use std::collections::HashMap;
 use std::env::consts::OS;
-use std::io;
 use std::io::Write;
+use std::rc::Rc;
 use std::sync::mpsc;
 
 mod core;
 mod models;
 mod platform;
 mod ui;
+
+const HISTORY_PATH: &str = "./history.toml";
+const CONFIG_PATH: &str = "./config.toml";
+
+// Every .toml of the extension folders, or what config.toml lists
+fn load_registry(sources: &ExtensionSources, current_os: Os) -> MasterRegistry {
+    MasterRegistry::from_sources(&sources.resolve(), current_os)
+}
+
+// Connects the palette UI to the registry and the platform
+struct Palette {
+    registry: MasterRegistry,
+    history: UsageHistory,
+    executor: ActionExecutor,
+    hotkeys: Rc<HotkeyHandle>,
+    hotkey_bindings: Vec<DirectHotkey>, // [[hotkeys]] of config.toml
+    direct_hotkeys: HashMap<HotkeyId, String>, // Action id, ie "chrome/new_tab"
+    sources: ExtensionSources,
+    current_os: Os,
+}
+
+impl Palette {
+    // Registered again after every reload, so a hotkey whose action was missing
+    // gets bound once the action shows up. Only [[hotkeys]] are bound: the cmd of
+    // a global action is the chord it sends, grabbing that chord would swallow the
+    // user's key and catch the injected one again
+    fn bind_hotkeys(&mut self) {
+        for (id, _) in self.direct_hotkeys.drain() {
+            if let Err(e) = self.hotkeys.unregister(id) {
+                warn!("{e}");
+            }
+        }
+        for hotkey in &self.hotkey_bindings {
+            if self.registry.find_action(&hotkey.action).is_none() {
+                warn!("Hotkey for {}: no such action", hotkey.action);
+                continue;
+            }
+            match self.hotkeys.register(KeyboardShortcut::from(&hotkey.keys)) {
+                Ok(id) => {
+                    self.direct_hotkeys.insert(id, hotkey.action.clone());
+                }
+                Err(e) => warn!("Hotkey for {}: {e}", hotkey.action),
+            }
+        }
+    }
+
+    fn execute(&mut self, action: &UnitAction) {
+        let registry = &self.registry;
+        if let Err(e) = self
+            .executor
+            .execute(action, &|id| registry.find_action(id))
+        {
+            error!("Failed to run {}: {e}", action.action_name);
+            return;
+        }
+
+        self.history.record(&action.id(), now_secs());
+        if let Err(e) = self.history.save(HISTORY_PATH) {
+            warn!("{e}");
+        }
+    }
+}
+
+impl PaletteBackend for Palette {
+    // Opening the palette stops a running macro
+    fn open(&mut self) -> Vec<UnitAction> {
+        self.executor.cancel();
+        self.executor.capture_target();
+
+        // The window we were opened over is the active one, whatever the z-order says
+        let mut context = get_all_context();
+        if let Some(target) = self.executor.target() {
+            context.fg_context.retain(|c| c != target);
+            context.fg_context.insert(0, *target);
+        }
+
+        self.registry.get_actions(&context)
+    }
+
+    fn history(&self) -> &UsageHistory {
+        &self.history
+    }
+
+    fn run(&mut self, action: &UnitAction) {
+        self.execute(action);
+    }
+
+    // No palette in between, the keys go to whatever has focus now.
+    // Looked up on every press so it follows extension reloads
+    fn run_hotkey(&mut self, id: HotkeyId) {
+        let Some(action) = self
+            .direct_hotkeys
+            .get(&id)
+            .and_then(|action_id| self.registry.find_action(action_id))
+        else {
+            warn!("No action bound to hotkey {id}");
+            return;
+        };
+        self.executor.capture_target();
+        self.execute(&action);
+    }
+
+    // Resolved again, a new file may match a glob of config.toml
+    fn reload_extensions(&mut self, paths: &[PathBuf]) {
+        self.registry
+            .reload(&self.sources.resolve(), paths, self.current_os);
+        self.bind_hotkeys();
+    }
+
+    fn extension_errors(&self) -> Vec<String> {
+        self.registry
+            .load_errors()
+            .into_iter()
+            .map(ToString::to_string)
+            .collect()
+    }
+}
 // fn main() {
 //     let (handle, rx) = platform::hotkey_actions::start_hotkey_listener();
 
@@ -57,74 +182,103 @@ fn main() {
 
     builder.init();
 
-    let current_os = match OS {
-        "windows" => Os::Windows,
-        "macos" => Os::Mac,
-        "linux" => Os::Linux,
-        _ => panic!("OS not supported"),
+    let current_os = Os::from_config_key(OS).expect("OS not supported");
+
+    // `global_palette <command>` runs on the command line instead, see ui::cli
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if let Some(code) = cli::run(&args, CONFIG_PATH.as_ref(), current_os) {
+        std::process::exit(code);
+    }
+
+    let app_config = match load_app_config(CONFIG_PATH) {
+        Ok(app_config) => app_config,
+        Err(e) => {
+            error!("{e}");
+            std::process::exit(1);
+        }
     };
 
     // UI channel
     let (ui_tx, ui_rx) = mpsc::channel::<UiSignal>();
 
     // Register and listen for hot keys
-    let (handle, rx) = platform::hotkey_actions::start_hotkey_listener();
+    let activation = KeyboardShortcut::from(&app_config.activation);
+    let (handle, rx) = match platform::hotkey_actions::start_hotkey_listener(activation) {
+        Ok(listener) => listener,
+        Err(e) => {
+            error!("{e}");
+            std::process::exit(1);
+        }
+    };
     let ui_tx_clone = ui_tx.clone();
     std::thread::spawn(move || {
         while let Ok(ev) = rx.recv() {
-            dbg!(ev);
-
-            // For now we do this
-            if ev.modifier.control && ev.modifier.shift && matches!(ev.key, Key::KeyP) {
-                match ui_tx_clone.send(UiSignal::ToggleVisibility) {
-                    Ok(_) => {
-                        info!("Successfully sent UiSignal::ToggleVisibility");
-                        std::io::stdout().flush().unwrap();
-                        // Give UI thread a moment to process
-                        std::thread::sleep(std::time::Duration::from_millis(100));
-                    }
-                    Err(e) => error!("Failed to send UiSignal: {e}"),
+            if ev.id != ACTIVATION_ID {
+                if let Err(e) = ui_tx_clone.send(UiSignal::Hotkey(ev.id)) {
+                    error!("Failed to send UiSignal: {e}");
                 }
+                continue;
+            }
+            match ui_tx_clone.send(UiSignal::ToggleVisibility) {
+                Ok(_) => {
+                    info!("Successfully sent UiSignal::ToggleVisibility");
+                    std::io::stdout().flush().unwrap();
+                    // Give UI thread a moment to process
+                    std::thread::sleep(std::time::Duration::from_millis(100));
+                }
+                Err(e) => error!("Failed to send UiSignal: {e}"),
             }
         }
     });
 
-    // Find and load extentions // This needs to be hot loaded in the future
-    let extensions_folder = Path::new("./extensions");
-    let master_registry = MasterRegistry::build(extensions_folder, current_os);
-    let context_root = get_all_context();
-
-    let avail_actions = master_registry.get_actions(&context_root);
-    // dbg!(&master_registry);
-    let process_names: AppProcessName = context_root
-        .fg_context
-        .iter()
-        .map(|h| h.get_app_process_name().unwrap_or("missing".into()))
-        .collect();
-    dbg!(&process_names);
-    dbg!(&avail_actions);
-
-    let mut user_input = String::new();
-
-    // 2. Read the line from stdin
-    // println!("Input sarch term: ");
-    // io::stdin()
-    //     .read_line(&mut user_input) // read_line appends the input to the string
-    //     .expect("Failed to read line"); // Handle potential errors
-    // let user_input: String = user_input.trim().to_string();
-
-    // let mut match_results: Vec<MatchResult> = vec![];
-    // for unit_action in avail_actions {
-    //     let match_res = get_score(&unit_action.action_name, &user_input);
-    //     match_results.push(match_res);
-    // }
-
-    // dbg!(match_results);
+    // Find and load extentions
+    let sources = ExtensionSources::new(&app_config);
+    let master_registry = load_registry(&sources, current_os);
+
+    let history = UsageHistory::load(HISTORY_PATH).unwrap_or_else(|e| {
+        warn!("{e}, starting with an empty history");
+        UsageHistory::default()
+    });
+
+    // The palette registers the direct hotkeys, main stops the listener at the end
+    let handle = Rc::new(handle);
+    let watch_dirs = sources.watch_dirs();
+    let mut palette = Palette {
+        registry: master_registry,
+        history,
+        executor: ActionExecutor::new(
+            platform::hotkey_actions::new_key_sender(),
+            Box::new(OsSpawner::new(current_os)),
+            Box::new(SystemClipboard::default()),
+        ),
+        hotkeys: Rc::clone(&handle),
+        hotkey_bindings: app_config.hotkeys.clone(),
+        direct_hotkeys: HashMap::new(),
+        sources,
+        current_os,
+    };
+    palette.bind_hotkeys();
+
+    // Reload extension files as they are edited
+    let watcher_tx = ui_tx.clone();
+    let _extension_watcher = watch_extensions(
+        &watch_dirs,
+        |p| p.extension().is_some_and(|e| e == "toml"),
+        move |paths| {
+            if let Err(e) = watcher_tx.send(UiSignal::ExtensionsChanged(paths)) {
+                error!("Failed to send UiSignal: {e}");
+            }
+        },
+    )
+    .inspect_err(|e| warn!("{e}, extensions will not reload"));
 
     // Run UI on the main thread (winit requires the event loop on main)
-    ui_main::ui_main(ui_rx);
+    ui_main::ui_main(ui_rx, Box::new(palette), app_config.ui);
 
-    // cleanup
-    handle.stop();
+    // cleanup, the listener unregisters the direct hotkeys as it stops
+    match Rc::try_unwrap(handle) {
+        Ok(handle) => handle.stop(),
+        Err(_) => warn!("Palette still holds the hotkey listener, it is not stopped"),
+    }
     // platform::hotkey_actions::send_ctrl_v();
 }
@@ -1,16 +1,105 @@
-use std::rc::Rc;
-
-use crate::models::hotkey::KeyboardShortcut;
+use crate::models::{
+    config::{Launch, TypeText},
+    hotkey::KeySequence,
+};
 use raw_window_handle::RawWindowHandle;
 use serde::Deserialize;
-
-use linked_hash_set::LinkedHashSet;
+use std::fmt;
 
 #[derive(Debug, Clone, Hash)]
 pub struct Action {
     pub name: String,
-    pub keyboard_shortcut: KeyboardShortcut,
+    pub kind: ActionKind,
     pub focus_state: FocusState,
+    pub aliases: Vec<String>,
+    pub description: Option<String>,
+    pub keywords: Vec<String>,
+    pub category: Option<String>,
+    pub priority: Priority, // Effective priority, the action override or the app default
+}
+
+// What running the action does
+#[derive(Debug, Clone, Hash)]
+pub enum ActionKind {
+    Shortcut(KeySequence),
+    Macro(Vec<MacroStep>),
+    Spawn(Spawn), // Needs no window, so it also works as a global action
+    TypeText(TypeText),
+}
+
+impl ActionKind {
+    // The other kinds have no chord of their own, so they never conflict
+    pub fn shortcut(&self) -> Option<&KeySequence> {
+        match self {
+            ActionKind::Shortcut(sequence) => Some(sequence),
+            ActionKind::Macro(_) | ActionKind::Spawn(_) | ActionKind::TypeText(_) => None,
+        }
+    }
+}
+
+// Renders the chords, or what kind of action it is
+impl fmt::Display for ActionKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ActionKind::Shortcut(sequence) => write!(f, "{sequence}"),
+            ActionKind::Macro(_) => write!(f, "Macro"),
+            ActionKind::Spawn(Spawn::Launch(_)) => write!(f, "Launch"),
+            ActionKind::Spawn(Spawn::Open(_)) => write!(f, "Open"),
+            ActionKind::Spawn(Spawn::Shell(_)) => write!(f, "Shell"),
+            ActionKind::TypeText(_) => write!(f, "Type text"),
+        }
+    }
+}
+
+// Starts something outside of the palette, see platform::spawner
+#[derive(Debug, Clone, PartialEq, Hash)]
+pub enum Spawn {
+    Launch(Launch),
+    Open(String),  // File, folder or URL
+    Shell(String), // Command line
+}
+
+// Renders as written in the extension file, ie "open https://github.com"
+impl fmt::Display for Spawn {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Spawn::Launch(launch) => {
+                write!(f, "launch {}", launch.program)?;
+                for arg in &launch.args {
+                    write!(f, " {arg}")?;
+                }
+                Ok(())
+            }
+            Spawn::Open(target) => write!(f, "open {target}"),
+            Spawn::Shell(command) => write!(f, "shell {command}"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Hash)]
+pub enum MacroStep {
+    Send(KeySequence),
+    Type(String),
+    Wait(u64),             // Milliseconds
+    Focus(AppProcessName), // First window of the process
+    Run(String),           // Action id, ie "chrome/new_tab"
+    Spawn(Spawn),
+    TypeText(TypeText), // Template, placeholders are filled in when the step runs
+}
+
+// Renders as written in the extension file, ie "wait_ms 300"
+impl fmt::Display for MacroStep {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MacroStep::Send(sequence) => write!(f, "send {sequence}"),
+            MacroStep::Type(text) => write!(f, "type {text:?}"),
+            MacroStep::Wait(ms) => write!(f, "wait_ms {ms}"),
+            MacroStep::Focus(process) => write!(f, "focus {process}"),
+            MacroStep::Run(id) => write!(f, "run {id}"),
+            MacroStep::Spawn(spawn) => write!(f, "{spawn}"),
+            MacroStep::TypeText(snippet) => write!(f, "type_text {:?}", snippet.text),
+        }
+    }
 }
 
 #[derive(Debug, Deserialize, Copy, Clone, PartialEq, Hash)]
@@ -21,13 +110,31 @@ pub enum FocusState {
     Global,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub enum Os {
     Windows,
     Mac,
     Linux,
 }
 
-#[derive(PartialEq, Eq, Debug)] // Debug is useful for printing
+impl Os {
+    // Key used for this OS in extension files, ie `cmd.linux`
+    pub fn config_key(&self) -> &'static str {
+        match self {
+            Os::Windows => "windows",
+            Os::Mac => "macos",
+            Os::Linux => "linux",
+        }
+    }
+
+    pub fn from_config_key(key: &str) -> Option<Os> {
+        [Os::Windows, Os::Mac, Os::Linux]
+            .into_iter()
+            .find(|os| os.config_key() == key)
+    }
+}
+
+#[derive(PartialEq, Eq, Debug, Clone, Copy, Hash)] // Debug is useful for printing
 #[repr(u8)]
 pub enum Priority {
     // Un-interceptable / always wins
@@ -54,12 +161,44 @@ pub enum Priority {
     // it’s an implementation detail (they *apply* a layer’s decision).
 }
 
-pub type ApplicationID = u32; // use to uniquely identify apps that may have same names
+impl Priority {
+    pub fn layer(&self) -> u8 {
+        *self as u8
+    }
+}
+
+// Order by layer, not by declaration order. A higher layer wins
+impl Ord for Priority {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        self.layer().cmp(&other.layer())
+    }
+}
+
+impl PartialOrd for Priority {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl From<crate::models::config::Priority> for Priority {
+    fn from(priority: crate::models::config::Priority) -> Self {
+        use crate::models::config::Priority as ConfigPriority;
+        match priority {
+            ConfigPriority::OSReserved => Priority::OSReserved,
+            ConfigPriority::GlobalRemapper => Priority::GlobalRemapper,
+            ConfigPriority::OSGlobal => Priority::OSGlobal,
+            ConfigPriority::UserOverrides => Priority::UserOverrides,
+            ConfigPriority::Application => Priority::Application,
+            ConfigPriority::ApplicationExtensions => Priority::ApplicationExtensions,
+            ConfigPriority::DocumentOrWebApp => Priority::DocumentOrWebApp,
+        }
+    }
+}
+
+pub type ApplicationID = String; // `app.id` of the extension file, ie "chrome". Unique across files
 pub type AppName = String; // Represent name of the app this action belongs to
 pub type AppProcessName = String;
-pub type ActionId = u32; // uniquely identifies what the user is trying to do, ie paste, copy, new tab
-                         // Not sure if u32 or string or some special struct is better
-                         // Open to change
+pub type ActionId = String; // Table name in the extension file, ie "new_tab". Unique within the app
 pub type ActionName = String;
 
 // All Context Root should have a mapping to all available actions that can be taken
@@ -72,14 +211,8 @@ pub struct ContextRoot {
 
 impl ContextRoot {
     pub fn get_active(&self) -> Option<&Context> {
-        return self.fg_context.first();
+        self.fg_context.first()
     }
 }
 
 type Context = RawWindowHandle;
-
-pub trait ContextExt {
-    fn get_all_names(&self) -> Vec<String>;
-}
-
-// impl ContextExt for ContextExt {}
@@ -0,0 +1,105 @@
+// Top level app settings from config.toml. Extension files use models::config
+
+use serde::Deserialize;
+
+use crate::models::{
+    config::{KeyChord, Modifier},
+    hotkey::Key,
+};
+
+#[derive(Debug, Deserialize)]
+pub struct AppConfig {
+    // Chord that toggles the palette
+    #[serde(default = "default_activation")]
+    pub activation: KeyChord,
+    // Folders extension files are looked up in, lowest precedence first.
+    // "{system}" and "{user}" stand for the install wide and per user folders
+    #[serde(default = "default_search_dirs")]
+    pub search_dirs: Vec<String>,
+    // Files or globs, looked up in every search dir. Later entries take precedence.
+    // Empty means every .toml of the search dirs
+    #[serde(default)]
+    pub extensions: Vec<ExtensionEntry>,
+    #[serde(default)]
+    pub ui: UiConfig,
+    // Chords that run an action straight away, without opening the palette
+    #[serde(default)]
+    pub hotkeys: Vec<DirectHotkey>,
+}
+
+// "chrome.toml" or { path = "chrome.toml", enabled = false }
+#[derive(Debug, Deserialize, Clone, PartialEq)]
+#[serde(untagged)]
+pub enum ExtensionEntry {
+    Path(String),
+    Table {
+        path: String,
+        #[serde(default = "default_enabled")]
+        enabled: bool,
+    },
+}
+
+impl ExtensionEntry {
+    pub fn path(&self) -> &str {
+        match self {
+            ExtensionEntry::Path(path) | ExtensionEntry::Table { path, .. } => path,
+        }
+    }
+
+    // A disabled entry removes its files even when another entry matches them
+    pub fn enabled(&self) -> bool {
+        match self {
+            ExtensionEntry::Path(_) => true,
+            ExtensionEntry::Table { enabled, .. } => *enabled,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct DirectHotkey {
+    pub keys: KeyChord,
+    pub action: String, // Action id, ie "chrome/new_tab"
+}
+
+#[derive(Debug, Deserialize, Clone)]
+#[serde(default)]
+pub struct UiConfig {
+    pub width: f32,
+    pub max_results: usize, // Rows shown at once
+}
+
+fn default_search_dirs() -> Vec<String> {
+    vec!["{system}".into(), "{user}".into(), "extensions".into()]
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+fn default_activation() -> KeyChord {
+    KeyChord {
+        mods: vec![Modifier::Ctrl, Modifier::Shift],
+        key: Key::KeyP,
+    }
+}
+
+impl Default for AppConfig {
+    fn default() -> Self {
+        AppConfig {
+            activation: default_activation(),
+            search_dirs: default_search_dirs(),
+            extensions: vec![],
+            ui: UiConfig::default(),
+            hotkeys: vec![],
+        }
+    }
+}
+
+impl Default for UiConfig {
+    fn default() -> Self {
+        UiConfig {
+            width: 600.0,
+            max_results: 8,
+        }
+    }
+}
@@ -1,13 +1,21 @@
 use serde::Deserialize;
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
+use toml::Spanned;
 
-use crate::models::{action::FocusState, hotkey::Key};
+use crate::models::{
+    action::{FocusState, Os},
+    hotkey::{HotkeyModifiers, Key, KeySequence, KeyboardShortcut},
+};
+
+// Current schema of an extension file, older versions are migrated into it by
+// core::extensions::migration
+pub const CURRENT_VERSION: u32 = 2;
 
 #[derive(Debug, Deserialize)]
 pub struct Config {
     pub version: u32,
     pub app: App,
-    pub actions: HashMap<String, Action>,
+    pub actions: HashMap<Spanned<String>, Action>, // Key span, to point errors at the table
 }
 
 #[derive(Debug, Deserialize, Clone)]
@@ -16,36 +24,180 @@ pub struct App {
     pub name: String,
     pub default_focus_state: Option<FocusState>,
     pub default_priority: Priority,
+    // Only needed for focused and background actions, global ones run anywhere
+    #[serde(default)]
     pub application_os_name: AppOsName,
 }
 
+// A value for each OS, missing where it does not apply
 #[derive(Debug, Deserialize, Clone)]
-pub struct AppOsName {
-    pub windows: Option<String>,
-    pub macos: Option<String>,
-    pub linux: Option<String>,
+pub struct ByOs<T> {
+    pub windows: Option<T>,
+    pub macos: Option<T>,
+    pub linux: Option<T>,
+}
+
+impl<T> Default for ByOs<T> {
+    fn default() -> Self {
+        ByOs {
+            windows: None,
+            macos: None,
+            linux: None,
+        }
+    }
 }
 
+impl<T> ByOs<T> {
+    pub fn for_os(&self, os: Os) -> Option<&T> {
+        match os {
+            Os::Windows => self.windows.as_ref(),
+            Os::Mac => self.macos.as_ref(),
+            Os::Linux => self.linux.as_ref(),
+        }
+    }
+}
+
+pub type AppOsName = ByOs<String>;
+pub type CmdByOs = ByOs<KeyBinding>;
+pub type StepsByOs = ByOs<Vec<Step>>;
+
 #[derive(Debug, Deserialize, Clone)]
 pub struct Action {
     pub name: String,
+    #[serde(default)]
+    pub aliases: Vec<String>, // Other names the user may type for this action
+    pub description: Option<String>,
+    #[serde(default)]
+    pub keywords: Vec<String>, // Extra search terms, not displayed
+    pub category: Option<String>,
     pub focus_state: Option<FocusState>,
+    pub priority: Option<Priority>, // Falls back to app.default_priority
+    // One of these for each OS
+    #[serde(default)]
     pub cmd: CmdByOs,
+    #[serde(default)]
+    pub steps: StepsByOs,
+    #[serde(default)]
+    pub launch: ByOs<Launch>,
+    #[serde(default)]
+    pub open: ByOs<String>, // File, folder or URL, opened with the desktop's default handler
+    #[serde(default)]
+    pub shell: ByOs<String>, // Command line for the shell of the OS
+    #[serde(default)]
+    pub type_text: ByOs<TypeText>,
+}
+
+// What `cmd.<os>` accepts:
+//   { mods = ["ctrl"], key = "t" }
+//   [{ mods = ["ctrl"], key = "k" }, { mods = ["ctrl"], key = "s" }]
+//   { sequence = [{ key = "g" }, { key = "i" }], delay_ms = 50 }
+#[derive(Debug, Deserialize, Clone)]
+#[serde(untagged)]
+pub enum KeyBinding {
+    Chord(KeyChord),
+    Sequence(Vec<KeyChord>),
+    Timed {
+        sequence: Vec<KeyChord>,
+        #[serde(default)]
+        delay_ms: u64,
+    },
+}
+
+impl KeyBinding {
+    pub fn chords(&self) -> &[KeyChord] {
+        match self {
+            KeyBinding::Chord(chord) => std::slice::from_ref(chord),
+            KeyBinding::Sequence(sequence) | KeyBinding::Timed { sequence, .. } => sequence,
+        }
+    }
 }
 
+impl From<&KeyBinding> for KeySequence {
+    fn from(binding: &KeyBinding) -> Self {
+        let delay_ms = match binding {
+            KeyBinding::Timed { delay_ms, .. } => *delay_ms,
+            _ => 0,
+        };
+        KeySequence {
+            chords: binding
+                .chords()
+                .iter()
+                .map(KeyboardShortcut::from)
+                .collect(),
+            delay_ms,
+        }
+    }
+}
+
+// A program started directly, without a shell:
+//   launch.windows = { program = "code", args = ["--new-window"], cwd = "C:/src", env = { RUST_LOG = "info" } }
+#[derive(Debug, Deserialize, Clone, PartialEq, Hash)]
+pub struct Launch {
+    pub program: String,
+    #[serde(default)]
+    pub args: Vec<String>,
+    pub cwd: Option<String>,
+    #[serde(default)]
+    pub env: BTreeMap<String, String>, // Added to the palette's environment
+}
+
+// A snippet typed into the focused window, see core::snippets for the placeholders:
+//   type_text.windows = { text = "Hi {clipboard},\nthanks for reaching out", delivery = "keys" }
+#[derive(Debug, Deserialize, Clone, PartialEq, Hash)]
+pub struct TypeText {
+    pub text: String,
+    #[serde(default)]
+    pub delivery: Delivery,
+}
+
+#[derive(Debug, Deserialize, Clone, Copy, Default, PartialEq, Hash)]
+#[serde(rename_all = "lowercase")]
+pub enum Delivery {
+    // Synthesized key presses, US layout ASCII only
+    Keys,
+    // Through the clipboard, which is restored afterwards. Any text, and fast for long
+    // replies, so the default: a placeholder can fill in text of any language
+    #[default]
+    Paste,
+}
+
+// A macro, run in order:
+//   steps.windows = [{ send = { mods = ["ctrl"], key = "L" } }, { wait_ms = 100 }, { type = "text" }]
 #[derive(Debug, Deserialize, Clone)]
-pub struct CmdByOs {
-    pub windows: Option<KeyChord>,
-    pub macos: Option<KeyChord>,
-    pub linux: Option<KeyChord>,
+#[serde(rename_all = "snake_case")]
+pub enum Step {
+    Send(KeyBinding),
+    Type(String),
+    WaitMs(u64),
+    Focus(String), // Process name, as in app.application_os_name
+    Run(String),   // Action id, "app/action", or the key of an action of this file
+    Launch(Launch),
+    Open(String),
+    Shell(String),
 }
 
 #[derive(Debug, Deserialize, Clone)]
 pub struct KeyChord {
+    #[serde(default)]
     pub mods: Vec<Modifier>,
     pub key: Key,
 }
 
+// Cmd is the macOS name of the Win/Super key. Fn never reaches the OS
+impl From<&KeyChord> for KeyboardShortcut {
+    fn from(chord: &KeyChord) -> Self {
+        KeyboardShortcut {
+            modifier: HotkeyModifiers {
+                control: chord.mods.contains(&Modifier::Ctrl),
+                shift: chord.mods.contains(&Modifier::Shift),
+                alt: chord.mods.contains(&Modifier::Alt),
+                win: chord.mods.contains(&Modifier::Win) || chord.mods.contains(&Modifier::Cmd),
+            },
+            key: chord.key,
+        }
+    }
+}
+
 #[derive(Debug, Deserialize, PartialEq, Eq, Clone, Copy)]
 #[serde(rename_all = "lowercase")]
 pub enum Modifier {
@@ -58,20 +210,21 @@ pub enum Modifier {
     // Meta,
 }
 
+// Version 1 spelled these in PascalCase, see models::config_v1
 #[derive(Debug, Deserialize, Clone, Copy)]
 pub enum Priority {
-    #[serde(rename = "OSReserved")]
+    #[serde(rename = "os_reserved")]
     OSReserved,
-    #[serde(rename = "GlobalRemapper")]
+    #[serde(rename = "global_remapper")]
     GlobalRemapper,
-    #[serde(rename = "OSGlobal")]
+    #[serde(rename = "os_global")]
     OSGlobal,
-    #[serde(rename = "UserOverrides")]
+    #[serde(rename = "user_overrides")]
     UserOverrides,
-    #[serde(rename = "Application")]
+    #[serde(rename = "application")]
     Application,
-    #[serde(rename = "ApplicationExtensions")]
+    #[serde(rename = "application_extensions")]
     ApplicationExtensions,
-    #[serde(rename = "DocumentOrWebApp")]
+    #[serde(rename = "document_or_web_app")]
     DocumentOrWebApp,
 }
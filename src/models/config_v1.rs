@@ -0,0 +1,51 @@
+// Version 1 of the extension schema, only read to be migrated to models::config.
+// Only the parts that changed have their own types here
+
+use serde::Deserialize;
+use std::collections::HashMap;
+use toml::Spanned;
+
+use crate::models::{
+    action::FocusState,
+    config::{AppOsName, CmdByOs},
+};
+
+#[derive(Debug, Deserialize)]
+pub struct ConfigV1 {
+    pub app: AppV1,
+    pub actions: HashMap<Spanned<String>, ActionV1>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct AppV1 {
+    pub id: String,
+    pub name: String,
+    pub default_focus_state: Option<FocusState>,
+    pub default_priority: Spanned<PriorityV1>,
+    pub application_os_name: AppOsName,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ActionV1 {
+    pub name: String,
+    #[serde(default)]
+    pub aliases: Vec<String>,
+    pub description: Option<String>,
+    #[serde(default)]
+    pub keywords: Vec<String>,
+    pub category: Option<String>,
+    pub focus_state: Option<FocusState>,
+    pub priority: Option<Spanned<PriorityV1>>,
+    pub cmd: CmdByOs,
+}
+
+#[derive(Debug, Deserialize, Clone, Copy)]
+pub enum PriorityV1 {
+    OSReserved,
+    GlobalRemapper,
+    OSGlobal,
+    UserOverrides,
+    Application,
+    ApplicationExtensions,
+    DocumentOrWebApp,
+}
@@ -0,0 +1,78 @@
+// A problem found in an extension file, precise enough for the author to fix it
+
+use std::{fmt, ops::Range, path::PathBuf};
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ExtensionError {
+    pub path: PathBuf,
+    pub span: Option<Range<usize>>, // Byte range in the file
+    pub line: Option<usize>,        // 1 based, from span
+    pub action: Option<String>,     // Key of the action table, ie "new_tab"
+    pub reason: String,
+}
+
+impl ExtensionError {
+    // Problem with the whole file
+    pub fn file(path: PathBuf, reason: impl Into<String>) -> Self {
+        ExtensionError {
+            path,
+            span: None,
+            line: None,
+            action: None,
+            reason: reason.into(),
+        }
+    }
+
+    // Problem at `span` of `source`, the text of the file
+    pub fn at(path: PathBuf, source: &str, span: Range<usize>, reason: impl Into<String>) -> Self {
+        let line = source
+            .get(..span.start)
+            .map(|before| before.matches('\n').count() + 1);
+        ExtensionError {
+            path,
+            span: Some(span),
+            line,
+            action: None,
+            reason: reason.into(),
+        }
+    }
+
+    pub fn in_action(mut self, action: &str) -> Self {
+        self.action = Some(action.to_string());
+        self
+    }
+}
+
+// "extensions/chrome.toml:12: new_tab: no cmd.linux binding"
+impl fmt::Display for ExtensionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.path.display())?;
+        if let Some(line) = self.line {
+            write!(f, ":{line}")?;
+        }
+        if let Some(action) = &self.action {
+            write!(f, ": {action}")?;
+        }
+        write!(f, ": {}", self.reason)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn points_at_the_line_of_the_span() {
+        let source = "version = 1\n\n[actions.new_tab]\nname = \"New tab\"\n";
+        let start = source.find("new_tab").unwrap();
+        let err = ExtensionError::at("chrome.toml".into(), source, start..start + 7, "no binding")
+            .in_action("new_tab");
+
+        assert_eq!(err.line, Some(3));
+        assert_eq!(err.to_string(), "chrome.toml:3: new_tab: no binding");
+        assert_eq!(
+            ExtensionError::file("chrome.toml".into(), "unreadable").to_string(),
+            "chrome.toml: unreadable"
+        );
+    }
+}
@@ -1,5 +1,9 @@
 use serde::Deserialize;
-use strum_macros::{Display, EnumString};
+use std::{
+    fmt,
+    hash::{Hash, Hasher},
+};
+use strum_macros::Display;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum Modifier {
@@ -10,7 +14,7 @@ pub enum Modifier {
 }
 
 // Optional: Implement a helper struct or method to hold a combination of modifiers
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
 
 pub struct HotkeyModifiers {
     pub control: bool,
@@ -19,18 +23,18 @@ pub struct HotkeyModifiers {
     pub win: bool,
 }
 
-impl Default for HotkeyModifiers {
-    fn default() -> Self {
-        HotkeyModifiers {
-            control: false,
-            shift: false,
-            alt: false,
-            win: false,
+impl HotkeyModifiers {
+    pub fn contains(&self, modifier: Modifier) -> bool {
+        match modifier {
+            Modifier::Control => self.control,
+            Modifier::Shift => self.shift,
+            Modifier::Alt => self.alt,
+            Modifier::Win => self.win,
         }
     }
 }
 
-#[derive(Debug, Clone, Copy, Hash, Deserialize, Display)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize, Display)]
 #[strum(serialize_all = "snake_case")] // Converts KeyA to "key_a" automatically
 pub enum Key {
     // --- 1. Alphanumeric Keys ---
@@ -121,6 +125,18 @@ pub enum Key {
     F10,
     F11,
     F12,
+    F13,
+    F14,
+    F15,
+    F16,
+    F17,
+    F18,
+    F19,
+    F20,
+    F21,
+    F22,
+    F23,
+    F24,
 
     // --- 3. Punctuation & Symbol Keys (New) ---
     // These generally map to the un-shifted key on the keyboard:
@@ -159,11 +175,237 @@ pub enum Key {
     RightArrow,
     UpArrow,
     DownArrow,
+
+    // --- 6. Numpad Keys ---
+    // Numpad enter is Enter, no platform tells them apart for a hotkey
+    Numpad0,
+    Numpad1,
+    Numpad2,
+    Numpad3,
+    Numpad4,
+    Numpad5,
+    Numpad6,
+    Numpad7,
+    Numpad8,
+    Numpad9,
+    NumpadAdd,
+    NumpadSubtract,
+    NumpadMultiply,
+    NumpadDivide,
+    NumpadDecimal,
+
+    // --- 7. Media & Browser Keys ---
+    MediaPlayPause,
+    MediaStop,
+    #[serde(alias = "MediaTrackNext")]
+    MediaNextTrack,
+    #[serde(alias = "MediaTrackPrevious")]
+    MediaPrevTrack,
+    #[serde(alias = "AudioVolumeMute")]
+    VolumeMute,
+    #[serde(alias = "AudioVolumeDown")]
+    VolumeDown,
+    #[serde(alias = "AudioVolumeUp")]
+    VolumeUp,
+    BrowserBack,
+    BrowserForward,
+    BrowserRefresh,
+    BrowserStop,
+    BrowserSearch,
+    BrowserFavorites,
+    BrowserHome,
+
+    // --- 8. Lock & System Keys ---
+    #[serde(alias = "Menu", alias = "Apps")]
+    ContextMenu,
+    CapsLock,
+    NumLock,
+
+    // --- 9. Side Specific Modifier Keys ---
+    // The modifier as the key of a chord, ie RightAlt on its own. Modifiers of a
+    // chord do not tell the sides apart
+    #[serde(alias = "ShiftLeft")]
+    LeftShift,
+    #[serde(alias = "ShiftRight")]
+    RightShift,
+    #[serde(alias = "ControlLeft", alias = "LeftCtrl")]
+    LeftControl,
+    #[serde(alias = "ControlRight", alias = "RightCtrl")]
+    RightControl,
+    #[serde(alias = "AltLeft")]
+    LeftAlt,
+    #[serde(alias = "AltRight")]
+    RightAlt,
+    #[serde(alias = "MetaLeft", alias = "LeftCmd")]
+    LeftWin,
+    #[serde(alias = "MetaRight", alias = "RightCmd")]
+    RightWin,
+
+    // --- 10. Anything Else ---
+    // Native code of the OS, a virtual key on Windows and a keysym on X11:
+    //   key = { raw = 0x1008ff1b }
+    // Keys without a name above come back from the listener as Raw
+    #[serde(alias = "raw")]
+    Raw(u32),
 }
 
-#[derive(Debug, Clone, Copy, Hash)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 
 pub struct KeyboardShortcut {
     pub modifier: HotkeyModifiers,
     pub key: Key,
 }
+
+// Chords pressed one after the other, ie "Ctrl+K Ctrl+S" in VS Code or "G I" in Gmail.
+// Most bindings are a single chord
+#[derive(Debug, Clone)]
+pub struct KeySequence {
+    pub chords: Vec<KeyboardShortcut>,
+    pub delay_ms: u64, // Pause between two chords, for apps that drop fast input
+}
+
+impl From<KeyboardShortcut> for KeySequence {
+    fn from(chord: KeyboardShortcut) -> Self {
+        KeySequence {
+            chords: vec![chord],
+            delay_ms: 0,
+        }
+    }
+}
+
+// The delay is how the keys are sent, not which keys, so two bindings with the
+// same chords still conflict
+impl PartialEq for KeySequence {
+    fn eq(&self, other: &Self) -> bool {
+        self.chords == other.chords
+    }
+}
+
+impl Eq for KeySequence {}
+
+impl KeySequence {
+    // Ctrl+K is a prefix of Ctrl+K Ctrl+S, a sequence is not a prefix of itself
+    pub fn is_prefix_of(&self, other: &KeySequence) -> bool {
+        self.chords.len() < other.chords.len() && other.chords.starts_with(&self.chords)
+    }
+}
+
+impl Hash for KeySequence {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.chords.hash(state);
+    }
+}
+
+impl Key {
+    // Human readable name used when rendering a shortcut, ie "T", "F5", "PageUp"
+    pub fn label(&self) -> String {
+        if let Key::Raw(code) = self {
+            return format!("Raw({code:#x})");
+        }
+        let name = self.to_string();
+        // "key_a" -> "a", "key1" -> "1"
+        let name = name
+            .strip_prefix("key_")
+            .or_else(|| name.strip_prefix("key"))
+            .unwrap_or(&name);
+        name.split('_')
+            .map(|part| {
+                let mut chars = part.chars();
+                match chars.next() {
+                    Some(first) => first.to_uppercase().chain(chars).collect(),
+                    None => String::new(),
+                }
+            })
+            .collect()
+    }
+}
+
+// Renders as "Ctrl+Shift+T". Also used as a search target so users can type the chord
+impl fmt::Display for KeyboardShortcut {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.modifier.control {
+            write!(f, "Ctrl+")?;
+        }
+        if self.modifier.shift {
+            write!(f, "Shift+")?;
+        }
+        if self.modifier.alt {
+            write!(f, "Alt+")?;
+        }
+        if self.modifier.win {
+            write!(f, "Win+")?;
+        }
+        write!(f, "{}", self.key.label())
+    }
+}
+
+// Renders as "Ctrl+K Ctrl+S"
+impl fmt::Display for KeySequence {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (idx, chord) in self.chords.iter().enumerate() {
+            if idx > 0 {
+                write!(f, " ")?;
+            }
+            write!(f, "{chord}")?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn renders_shortcut() {
+        let shortcut = KeyboardShortcut {
+            modifier: HotkeyModifiers {
+                control: true,
+                shift: true,
+                ..Default::default()
+            },
+            key: Key::KeyT,
+        };
+        assert_eq!(shortcut.to_string(), "Ctrl+Shift+T");
+        assert_eq!(Key::PageUp.label(), "PageUp");
+        assert_eq!(Key::F5.label(), "F5");
+        assert_eq!(Key::Key1.label(), "1");
+        assert_eq!(Key::Numpad1.label(), "Numpad1");
+        assert_eq!(Key::MediaPlayPause.label(), "MediaPlayPause");
+        assert_eq!(Key::Raw(0xad).label(), "Raw(0xad)");
+
+        let sequence = KeySequence {
+            chords: vec![
+                KeyboardShortcut {
+                    modifier: HotkeyModifiers::default(),
+                    key: Key::KeyG,
+                },
+                KeyboardShortcut {
+                    modifier: HotkeyModifiers::default(),
+                    key: Key::KeyI,
+                },
+            ],
+            delay_ms: 50,
+        };
+        assert_eq!(sequence.to_string(), "G I");
+        assert_eq!(KeySequence::from(shortcut).to_string(), "Ctrl+Shift+T");
+    }
+
+    #[test]
+    fn parses_named_and_raw_keys() {
+        #[derive(Deserialize)]
+        struct Binding {
+            key: Key,
+        }
+        let key = |source: &str| toml::from_str::<Binding>(source).map(|b| b.key);
+
+        assert_eq!(key(r#"key = "F13""#).unwrap(), Key::F13);
+        assert_eq!(key(r#"key = "AudioVolumeUp""#).unwrap(), Key::VolumeUp);
+        assert_eq!(key(r#"key = "Apps""#).unwrap(), Key::ContextMenu);
+        assert_eq!(
+            key("key = { raw = 0x1008ff2c }").unwrap(),
+            Key::Raw(0x1008ff2c)
+        );
+        assert!(key(r#"key = "Hyper""#).is_err());
+    }
+}
@@ -1,4 +1,7 @@
 pub mod action;
+pub mod app_config;
 pub mod config;
+pub mod config_v1;
+pub mod extension_error;
 pub mod hotkey;
 pub mod registry;
@@ -0,0 +1,62 @@
+// Text clipboard, used by the snippets of type_text actions
+
+#[cfg(test)]
+use std::sync::{Arc, Mutex};
+
+use log::warn;
+
+pub trait Clipboard {
+    // None when the clipboard is empty or holds something else than text
+    fn get_text(&mut self) -> Option<String>;
+    fn set_text(&mut self, text: &str) -> Result<(), String>;
+}
+
+// The OS clipboard. On X11 the owner of the clipboard serves its content, so the
+// connection is opened on first use and kept for as long as the palette runs
+#[derive(Default)]
+pub struct SystemClipboard {
+    clipboard: Option<arboard::Clipboard>,
+}
+
+impl SystemClipboard {
+    fn clipboard(&mut self) -> Result<&mut arboard::Clipboard, String> {
+        if self.clipboard.is_none() {
+            let clipboard =
+                arboard::Clipboard::new().map_err(|e| format!("No clipboard available: {e}"))?;
+            self.clipboard = Some(clipboard);
+        }
+        Ok(self.clipboard.as_mut().expect("clipboard was just opened"))
+    }
+}
+
+impl Clipboard for SystemClipboard {
+    fn get_text(&mut self) -> Option<String> {
+        let clipboard = self.clipboard().inspect_err(|e| warn!("{e}")).ok()?;
+        clipboard.get_text().ok()
+    }
+
+    fn set_text(&mut self, text: &str) -> Result<(), String> {
+        self.clipboard()?
+            .set_text(text)
+            .map_err(|e| format!("Could not set the clipboard: {e}"))
+    }
+}
+
+// In-memory clipboard for tests. Clones share the content
+#[cfg(test)]
+#[derive(Debug, Default, Clone)]
+pub struct MemoryClipboard {
+    text: Arc<Mutex<Option<String>>>,
+}
+
+#[cfg(test)]
+impl Clipboard for MemoryClipboard {
+    fn get_text(&mut self) -> Option<String> {
+        self.text.lock().unwrap().clone()
+    }
+
+    fn set_text(&mut self, text: &str) -> Result<(), String> {
+        *self.text.lock().unwrap() = Some(text.to_string());
+        Ok(())
+    }
+}
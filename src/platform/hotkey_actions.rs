@@ -1,30 +1,99 @@
+#[cfg(not(target_os = "windows"))]
+use crate::platform::key_sender::KeyEvent;
+use crate::platform::key_sender::KeySender;
+
 #[cfg(target_os = "windows")]
 pub use crate::platform::windows::receiver::hotkey_receiver::*;
+
+// Key injection for the current OS. Callers only see the KeySender trait
 #[cfg(target_os = "windows")]
-pub use crate::platform::windows::sender::hotkey_sender::*;
+pub fn new_key_sender() -> Box<dyn KeySender + Send> {
+    Box::new(crate::platform::windows::sender::hotkey_sender::WindowsKeySender)
+}
+
+#[cfg(target_os = "linux")]
+pub use crate::platform::linux::receiver::hotkey_receiver::*;
+
+// XTest on X11. Wayland goes through uinput, synthetic X events only reach
+// XWayland windows there, which is still better than nothing if uinput is denied
+#[cfg(target_os = "linux")]
+pub fn new_key_sender() -> Box<dyn KeySender + Send> {
+    use crate::platform::linux::sender::{
+        hotkey_sender::XTestKeySender, uinput_sender::UinputKeySender,
+    };
+    use log::{error, warn};
+
+    let wayland = std::env::var("XDG_SESSION_TYPE").is_ok_and(|session| session == "wayland");
+    if !wayland {
+        match XTestKeySender::connect() {
+            Ok(sender) => return Box::new(sender),
+            Err(e) => warn!("{e}, falling back to uinput"),
+        }
+    }
+
+    match UinputKeySender::open() {
+        Ok(sender) => Box::new(sender),
+        Err(e) => {
+            if wayland {
+                if let Ok(sender) = XTestKeySender::connect() {
+                    warn!("{e}, only XWayland windows will receive keys");
+                    return Box::new(sender);
+                }
+            }
+            error!("No way to inject keys: {e}");
+            Box::new(UnsupportedKeySender)
+        }
+    }
+}
+
+#[cfg(not(target_os = "windows"))]
+pub struct UnsupportedKeySender;
 
 #[cfg(not(target_os = "windows"))]
+impl KeySender for UnsupportedKeySender {
+    fn send(&mut self, _events: &[KeyEvent]) -> Result<(), String> {
+        Err("No key injection backend is available".into())
+    }
+}
+
+#[cfg(not(any(target_os = "windows", target_os = "linux")))]
 mod stub {
     use std::sync::mpsc::{self, Receiver};
 
-    #[derive(Debug, Clone, Copy)]
-    pub struct HotkeyEvent {
-        pub id: i32,
-        pub vk: u32,
-        pub modifiers: u32,
-    }
+    use crate::{
+        models::hotkey::KeyboardShortcut,
+        platform::{
+            hotkey_actions::UnsupportedKeySender,
+            key_sender::KeySender,
+            register_receiver::{HotkeyEvent, HotkeyId},
+        },
+    };
 
     pub struct HotkeyHandle;
     impl HotkeyHandle {
+        pub fn register(&self, shortcut: KeyboardShortcut) -> Result<HotkeyId, String> {
+            Err(format!(
+                "Cannot register {shortcut}, hotkeys are not supported on this OS"
+            ))
+        }
+        pub fn unregister(&self, _id: HotkeyId) -> Result<(), String> {
+            Ok(())
+        }
         pub fn stop(self) {}
     }
 
-    pub fn start_hotkey_listener() -> (HotkeyHandle, Receiver<HotkeyEvent>) {
-        // No-op on non-Windows for now
+    pub fn start_hotkey_listener(
+        _activation: KeyboardShortcut,
+    ) -> Result<(HotkeyHandle, Receiver<HotkeyEvent>), String> {
+        // No-op on this OS for now
         let (_tx, rx) = mpsc::channel();
-        (HotkeyHandle, rx)
+        Ok((HotkeyHandle, rx))
+    }
+
+    pub fn new_key_sender() -> Box<dyn KeySender + Send> {
+        Box::new(UnsupportedKeySender)
     }
 }
 
-#[cfg(not(target_os = "windows"))]
+#[cfg(not(any(target_os = "windows", target_os = "linux")))]
 pub use stub::*;
@@ -0,0 +1,289 @@
+// Platform neutral key injection. Each OS only implements the raw key events,
+// the chord sequencing lives here so it behaves the same everywhere.
+
+#[cfg(test)]
+use std::sync::{Arc, Mutex};
+
+use crate::models::hotkey::{HotkeyModifiers, Key, KeyboardShortcut, Modifier};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KeyCode {
+    Modifier(Modifier),
+    Key(Key),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KeyEvent {
+    Down(KeyCode),
+    Up(KeyCode),
+}
+
+// Press order. Released in reverse
+const MODIFIER_ORDER: [Modifier; 4] = [
+    Modifier::Control,
+    Modifier::Shift,
+    Modifier::Alt,
+    Modifier::Win,
+];
+
+pub trait KeySender {
+    // Inject the events in order, as one batch where the platform allows it
+    fn send(&mut self, events: &[KeyEvent]) -> Result<(), String>;
+
+    // Modifiers that are physically held right now, ie the user is still holding
+    // the palette hotkey. Backends that cannot tell report none.
+    fn held_modifiers(&self) -> HotkeyModifiers {
+        HotkeyModifiers::default()
+    }
+
+    fn send_shortcut(&mut self, shortcut: &KeyboardShortcut) -> Result<(), String> {
+        let events = shortcut_events(shortcut, &self.held_modifiers());
+        self.send(&events)
+    }
+
+    // Typed as key presses of a US layout. See text_events
+    fn type_text(&mut self, text: &str) -> Result<(), String> {
+        let events = text_events(text, &self.held_modifiers())?;
+        self.send(&events)
+    }
+
+    // Ctrl+C and Ctrl+V, for the clipboard paths of type_text. A macOS backend
+    // would send Cmd instead
+    fn copy(&mut self) -> Result<(), String> {
+        self.send_shortcut(&ctrl(Key::KeyC))
+    }
+
+    fn paste(&mut self) -> Result<(), String> {
+        self.send_shortcut(&ctrl(Key::KeyV))
+    }
+}
+
+fn ctrl(key: Key) -> KeyboardShortcut {
+    KeyboardShortcut {
+        modifier: HotkeyModifiers {
+            control: true,
+            ..Default::default()
+        },
+        key,
+    }
+}
+
+// Held modifiers are released first so they do not corrupt the injected keys
+fn release_held(held: &HotkeyModifiers) -> Vec<KeyEvent> {
+    MODIFIER_ORDER
+        .into_iter()
+        .filter(|modifier| held.contains(*modifier))
+        .map(|modifier| KeyEvent::Up(KeyCode::Modifier(modifier)))
+        .collect()
+}
+
+pub fn shortcut_events(shortcut: &KeyboardShortcut, held: &HotkeyModifiers) -> Vec<KeyEvent> {
+    let mut events = release_held(held);
+
+    for modifier in MODIFIER_ORDER {
+        if shortcut.modifier.contains(modifier) {
+            events.push(KeyEvent::Down(KeyCode::Modifier(modifier)));
+        }
+    }
+
+    events.push(KeyEvent::Down(KeyCode::Key(shortcut.key)));
+    events.push(KeyEvent::Up(KeyCode::Key(shortcut.key)));
+
+    for modifier in MODIFIER_ORDER.iter().rev() {
+        if shortcut.modifier.contains(*modifier) {
+            events.push(KeyEvent::Up(KeyCode::Modifier(*modifier)));
+        }
+    }
+
+    events
+}
+
+// Printable ASCII, tab and new line. Anything else has no key on a US layout
+pub fn text_events(text: &str, held: &HotkeyModifiers) -> Result<Vec<KeyEvent>, String> {
+    let mut events = release_held(held);
+    for c in text.chars() {
+        let (key, shift) = char_key(c).ok_or_else(|| format!("Cannot type {c:?} as keys"))?;
+        let chord = KeyboardShortcut {
+            modifier: HotkeyModifiers {
+                shift,
+                ..Default::default()
+            },
+            key,
+        };
+        events.extend(shortcut_events(&chord, &HotkeyModifiers::default()));
+    }
+    Ok(events)
+}
+
+// Unshifted and shifted character of each symbol key
+const SYMBOL_KEYS: &[(char, char, Key)] = &[
+    ('1', '!', Key::Key1),
+    ('2', '@', Key::Key2),
+    ('3', '#', Key::Key3),
+    ('4', '$', Key::Key4),
+    ('5', '%', Key::Key5),
+    ('6', '^', Key::Key6),
+    ('7', '&', Key::Key7),
+    ('8', '*', Key::Key8),
+    ('9', '(', Key::Key9),
+    ('0', ')', Key::Key0),
+    ('-', '_', Key::Minus),
+    ('=', '+', Key::Equal),
+    ('[', '{', Key::LeftBracket),
+    (']', '}', Key::RightBracket),
+    ('\\', '|', Key::Backslash),
+    (';', ':', Key::Semicolon),
+    ('\'', '"', Key::Apostrophe),
+    (',', '<', Key::Comma),
+    ('.', '>', Key::Period),
+    ('/', '?', Key::Slash),
+    ('`', '~', Key::Grave),
+];
+
+const LETTER_KEYS: [Key; 26] = [
+    Key::KeyA,
+    Key::KeyB,
+    Key::KeyC,
+    Key::KeyD,
+    Key::KeyE,
+    Key::KeyF,
+    Key::KeyG,
+    Key::KeyH,
+    Key::KeyI,
+    Key::KeyJ,
+    Key::KeyK,
+    Key::KeyL,
+    Key::KeyM,
+    Key::KeyN,
+    Key::KeyO,
+    Key::KeyP,
+    Key::KeyQ,
+    Key::KeyR,
+    Key::KeyS,
+    Key::KeyT,
+    Key::KeyU,
+    Key::KeyV,
+    Key::KeyW,
+    Key::KeyX,
+    Key::KeyY,
+    Key::KeyZ,
+];
+
+// Key and whether it needs Shift
+fn char_key(c: char) -> Option<(Key, bool)> {
+    match c {
+        'a'..='z' => Some((LETTER_KEYS[c as usize - 'a' as usize], false)),
+        'A'..='Z' => Some((LETTER_KEYS[c as usize - 'A' as usize], true)),
+        ' ' => Some((Key::Space, false)),
+        '\t' => Some((Key::Tab, false)),
+        '\n' => Some((Key::Enter, false)),
+        _ => SYMBOL_KEYS.iter().find_map(|(plain, shifted, key)| {
+            (c == *plain || c == *shifted).then_some((*key, c == *shifted))
+        }),
+    }
+}
+
+// In-memory backend for tests. Clones share the log, so a test can keep one
+// while the other is boxed into an executor.
+#[cfg(test)]
+#[derive(Debug, Default, Clone)]
+pub struct RecordingKeySender {
+    events: Arc<Mutex<Vec<KeyEvent>>>,
+    pub held: HotkeyModifiers, // Pretend these are physically held
+}
+
+#[cfg(test)]
+impl RecordingKeySender {
+    pub fn events(&self) -> Vec<KeyEvent> {
+        self.events.lock().unwrap().clone()
+    }
+}
+
+#[cfg(test)]
+impl KeySender for RecordingKeySender {
+    fn send(&mut self, events: &[KeyEvent]) -> Result<(), String> {
+        self.events.lock().unwrap().extend_from_slice(events);
+        Ok(())
+    }
+
+    fn held_modifiers(&self) -> HotkeyModifiers {
+        self.held
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sends_ctrl_shift_chord_in_order() {
+        let mut sender = RecordingKeySender::default();
+        let shortcut = KeyboardShortcut {
+            modifier: HotkeyModifiers {
+                control: true,
+                shift: true,
+                ..Default::default()
+            },
+            key: Key::KeyT,
+        };
+        sender.send_shortcut(&shortcut).unwrap();
+
+        assert_eq!(
+            sender.events(),
+            vec![
+                KeyEvent::Down(KeyCode::Modifier(Modifier::Control)),
+                KeyEvent::Down(KeyCode::Modifier(Modifier::Shift)),
+                KeyEvent::Down(KeyCode::Key(Key::KeyT)),
+                KeyEvent::Up(KeyCode::Key(Key::KeyT)),
+                KeyEvent::Up(KeyCode::Modifier(Modifier::Shift)),
+                KeyEvent::Up(KeyCode::Modifier(Modifier::Control)),
+            ]
+        );
+    }
+
+    #[test]
+    fn types_text_with_shift_for_upper_case_and_symbols() {
+        let shift = KeyCode::Modifier(Modifier::Shift);
+        assert_eq!(
+            text_events("a!", &HotkeyModifiers::default()).unwrap(),
+            vec![
+                KeyEvent::Down(KeyCode::Key(Key::KeyA)),
+                KeyEvent::Up(KeyCode::Key(Key::KeyA)),
+                KeyEvent::Down(shift),
+                KeyEvent::Down(KeyCode::Key(Key::Key1)),
+                KeyEvent::Up(KeyCode::Key(Key::Key1)),
+                KeyEvent::Up(shift),
+            ]
+        );
+        assert!(text_events("Hello, world?\n", &HotkeyModifiers::default()).is_ok());
+        assert!(text_events("café", &HotkeyModifiers::default()).is_err());
+    }
+
+    #[test]
+    fn held_modifiers_are_released_before_the_chord() {
+        let shortcut = KeyboardShortcut {
+            modifier: HotkeyModifiers {
+                control: true,
+                ..Default::default()
+            },
+            key: Key::KeyT,
+        };
+        let held = HotkeyModifiers {
+            control: true,
+            shift: true,
+            ..Default::default()
+        };
+
+        assert_eq!(
+            shortcut_events(&shortcut, &held),
+            vec![
+                KeyEvent::Up(KeyCode::Modifier(Modifier::Control)),
+                KeyEvent::Up(KeyCode::Modifier(Modifier::Shift)),
+                KeyEvent::Down(KeyCode::Modifier(Modifier::Control)),
+                KeyEvent::Down(KeyCode::Key(Key::KeyT)),
+                KeyEvent::Up(KeyCode::Key(Key::KeyT)),
+                KeyEvent::Up(KeyCode::Modifier(Modifier::Control)),
+            ]
+        );
+    }
+}
@@ -0,0 +1,255 @@
+// X11 window discovery through the EWMH properties the window manager keeps on
+// the root window. Wayland sessions are only covered through XWayland.
+
+use std::{fs, num::NonZeroU32, sync::OnceLock};
+
+use raw_window_handle::{RawWindowHandle, XcbWindowHandle};
+use x11rb::{
+    connection::Connection,
+    protocol::xproto::{
+        Atom, AtomEnum, ClientMessageEvent, ConnectionExt, EventMask, MapState, Window,
+    },
+    rust_connection::RustConnection,
+};
+
+// Longest property we read, in 32 bit units. _NET_CLIENT_LIST of a busy desktop
+// is a few hundred windows
+const MAX_PROPERTY_LEN: u32 = 4096;
+
+// The kernel keeps the first 15 bytes of the executable name as comm
+const COMM_LEN: usize = 15;
+
+// Opened on first use and shared, the palette asks for the name of every window
+// each time it opens. None when there is no X server, eg a pure Wayland session
+static X11_CONNECTION: OnceLock<Option<X11>> = OnceLock::new();
+
+struct Atoms {
+    net_active_window: Atom,
+    net_client_list: Atom,
+    net_wm_pid: Atom,
+    net_wm_name: Atom,
+}
+
+struct X11 {
+    conn: RustConnection,
+    root: Window,
+    atoms: Atoms,
+}
+
+impl X11 {
+    fn shared() -> Option<&'static X11> {
+        X11_CONNECTION.get_or_init(X11::connect).as_ref()
+    }
+
+    fn connect() -> Option<X11> {
+        let (conn, screen) = x11rb::connect(None).ok()?;
+        let root = conn.setup().roots.get(screen)?.root;
+
+        let intern = |name: &[u8]| -> Option<Atom> {
+            Some(conn.intern_atom(false, name).ok()?.reply().ok()?.atom)
+        };
+        let atoms = Atoms {
+            net_active_window: intern(b"_NET_ACTIVE_WINDOW")?,
+            net_client_list: intern(b"_NET_CLIENT_LIST")?,
+            net_wm_pid: intern(b"_NET_WM_PID")?,
+            net_wm_name: intern(b"_NET_WM_NAME")?,
+        };
+
+        Some(X11 { conn, root, atoms })
+    }
+
+    fn property32(&self, window: Window, property: Atom, type_: AtomEnum) -> Vec<u32> {
+        self.conn
+            .get_property(false, window, property, type_, 0, MAX_PROPERTY_LEN)
+            .ok()
+            .and_then(|cookie| cookie.reply().ok())
+            .and_then(|reply| reply.value32().map(|values| values.collect()))
+            .unwrap_or_default()
+    }
+
+    fn property8(&self, window: Window, property: Atom, type_: AtomEnum) -> Vec<u8> {
+        self.conn
+            .get_property(false, window, property, type_, 0, MAX_PROPERTY_LEN)
+            .ok()
+            .and_then(|cookie| cookie.reply().ok())
+            .map(|reply| reply.value)
+            .unwrap_or_default()
+    }
+
+    fn active_window(&self) -> Option<Window> {
+        self.property32(self.root, self.atoms.net_active_window, AtomEnum::WINDOW)
+            .first()
+            .copied()
+            .filter(|&window| window != x11rb::NONE)
+    }
+
+    fn is_viewable(&self, window: Window) -> bool {
+        self.conn
+            .get_window_attributes(window)
+            .ok()
+            .and_then(|cookie| cookie.reply().ok())
+            .is_some_and(|attributes| attributes.map_state == MapState::VIEWABLE)
+    }
+}
+
+fn to_handle(window: Window) -> Option<RawWindowHandle> {
+    NonZeroU32::new(window).map(|w| RawWindowHandle::Xcb(XcbWindowHandle::new(w)))
+}
+
+// Xlib and xcb handles name the same server side window
+pub fn get_window_from_raw(handle: RawWindowHandle) -> Option<Window> {
+    match handle {
+        RawWindowHandle::Xcb(h) => Some(h.window.get()),
+        RawWindowHandle::Xlib(h) => Window::try_from(h.window).ok(),
+        _ => None, // It's not an X11 handle
+    }
+}
+
+pub fn get_foreground_window() -> Option<RawWindowHandle> {
+    let x11 = X11::shared()?;
+    x11.active_window().and_then(to_handle)
+}
+
+// Mapped client windows are foreground, minimised ones or ones on another
+// workspace are background. The active window goes first.
+pub fn get_all_windows() -> (Vec<RawWindowHandle>, Vec<RawWindowHandle>) {
+    let Some(x11) = X11::shared() else {
+        return (vec![], vec![]);
+    };
+
+    let mut clients = x11.property32(x11.root, x11.atoms.net_client_list, AtomEnum::WINDOW);
+    if let Some(active) = x11.active_window() {
+        if let Some(pos) = clients.iter().position(|&w| w == active) {
+            clients.remove(pos);
+            clients.insert(0, active);
+        }
+    }
+
+    let mut fg = vec![];
+    let mut bg = vec![];
+    for window in clients {
+        let Some(handle) = to_handle(window) else {
+            continue;
+        };
+        if x11.is_viewable(window) {
+            fg.push(handle);
+        } else {
+            bg.push(handle);
+        }
+    }
+    (fg, bg)
+}
+
+// Ask the window manager to activate the window. Source indication 2 marks the
+// request as coming from a pager, which focus stealing prevention lets through
+pub fn focus_window(window: Window) -> bool {
+    let Some(x11) = X11::shared() else {
+        return false;
+    };
+
+    let event = ClientMessageEvent::new(
+        32,
+        window,
+        x11.atoms.net_active_window,
+        [2, x11rb::CURRENT_TIME, 0, 0, 0],
+    );
+    let sent = x11.conn.send_event(
+        false,
+        x11.root,
+        EventMask::SUBSTRUCTURE_REDIRECT | EventMask::SUBSTRUCTURE_NOTIFY,
+        event,
+    );
+    sent.is_ok() && x11.conn.flush().is_ok()
+}
+
+// Process name as matched against `AppOsName.linux`, always the kernel's comm
+// ie "chrome". Clients that do not set _NET_WM_PID get the WM_CLASS instance name
+// cut like comm, which is the executable name for most toolkits
+pub fn get_app_process_name(window: Window) -> Option<String> {
+    let x11 = X11::shared()?;
+
+    let pid = x11
+        .property32(window, x11.atoms.net_wm_pid, AtomEnum::CARDINAL)
+        .first()
+        .copied();
+    if let Some(name) = pid.and_then(process_name) {
+        return Some(name);
+    }
+
+    let wm_class = x11.property8(window, AtomEnum::WM_CLASS.into(), AtomEnum::STRING);
+    parse_wm_class(&wm_class).map(|(instance, _)| to_comm(&instance))
+}
+
+// _NET_WM_NAME is UTF-8. WM_NAME is the ICCCM fallback, in Latin-1
+pub fn get_window_title(window: Window) -> Option<String> {
+    let x11 = X11::shared()?;
+
+    let name = x11.property8(window, x11.atoms.net_wm_name, AtomEnum::ANY);
+    let title = if name.is_empty() {
+        let name = x11.property8(window, AtomEnum::WM_NAME.into(), AtomEnum::STRING);
+        name.into_iter().map(char::from).collect()
+    } else {
+        String::from_utf8_lossy(&name).into_owned()
+    };
+    (!title.is_empty()).then_some(title)
+}
+
+pub fn process_name(pid: u32) -> Option<String> {
+    let comm = fs::read_to_string(format!("/proc/{pid}/comm")).ok()?;
+    let comm = comm.trim_end();
+    (!comm.is_empty()).then(|| comm.to_string())
+}
+
+fn to_comm(name: &str) -> String {
+    let mut end = name.len().min(COMM_LEN);
+    while !name.is_char_boundary(end) {
+        end -= 1;
+    }
+    name[..end].to_string()
+}
+
+// WM_CLASS is two NUL terminated strings, the instance name then the class name
+fn parse_wm_class(value: &[u8]) -> Option<(String, String)> {
+    let mut parts = value
+        .split(|&b| b == 0)
+        .map(|part| String::from_utf8_lossy(part).into_owned());
+    let instance = parts.next()?;
+    let class = parts.next().filter(|class| !class.is_empty())?;
+    Some((instance, class))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_wm_class() {
+        assert_eq!(
+            parse_wm_class(b"google-chrome\0Google-chrome\0"),
+            Some(("google-chrome".into(), "Google-chrome".into()))
+        );
+        assert_eq!(parse_wm_class(b"xterm\0"), None);
+        assert_eq!(parse_wm_class(b""), None);
+    }
+
+    #[test]
+    fn wm_class_instance_is_cut_like_comm() {
+        assert_eq!(to_comm("xterm"), "xterm");
+        assert_eq!(to_comm("gnome-terminal-server"), "gnome-terminal-");
+        assert_eq!(to_comm("ééééééééé"), "ééééééé");
+    }
+
+    #[test]
+    fn reads_own_process_name() {
+        let name = process_name(std::process::id()).expect("own comm is readable");
+        assert!(!name.is_empty());
+        assert!(!name.ends_with('\n'));
+    }
+
+    #[test]
+    fn handles_round_trip() {
+        let handle = to_handle(0x0140_0003).unwrap();
+        assert_eq!(get_window_from_raw(handle), Some(0x0140_0003));
+        assert_eq!(to_handle(x11rb::NONE), None);
+    }
+}
@@ -0,0 +1,360 @@
+use x11rb::{
+    connection::Connection,
+    protocol::xproto::{ConnectionExt, KeyButMask, Keysym, ModMask},
+};
+
+use crate::models::hotkey::{HotkeyModifiers, Key, Modifier};
+
+// Lock modifiers the user can have toggled on. A grab only matches the exact
+// modifier state, so every chord is grabbed once per combination of these
+const CAPS_LOCK: u16 = 1 << 1; // ModMask::LOCK
+const NUM_LOCK: u16 = 1 << 4; // ModMask::M2 on every common layout
+pub const LOCK_MASKS: [u16; 4] = [0, CAPS_LOCK, NUM_LOCK, CAPS_LOCK | NUM_LOCK];
+
+// Key to X keysym (X11/keysymdef.h) and evdev code (linux/input-event-codes.h).
+// Letters use the lower case keysym, which is what the first column of the
+// keyboard mapping holds. Evdev codes are physical US layout positions.
+const KEY_TABLE: &[(Key, Keysym, u16)] = &[
+    // --- 1. Alphanumeric Keys ---
+    (Key::KeyA, 0x0061, 30),
+    (Key::KeyB, 0x0062, 48),
+    (Key::KeyC, 0x0063, 46),
+    (Key::KeyD, 0x0064, 32),
+    (Key::KeyE, 0x0065, 18),
+    (Key::KeyF, 0x0066, 33),
+    (Key::KeyG, 0x0067, 34),
+    (Key::KeyH, 0x0068, 35),
+    (Key::KeyI, 0x0069, 23),
+    (Key::KeyJ, 0x006a, 36),
+    (Key::KeyK, 0x006b, 37),
+    (Key::KeyL, 0x006c, 38),
+    (Key::KeyM, 0x006d, 50),
+    (Key::KeyN, 0x006e, 49),
+    (Key::KeyO, 0x006f, 24),
+    (Key::KeyP, 0x0070, 25),
+    (Key::KeyQ, 0x0071, 16),
+    (Key::KeyR, 0x0072, 19),
+    (Key::KeyS, 0x0073, 31),
+    (Key::KeyT, 0x0074, 20),
+    (Key::KeyU, 0x0075, 22),
+    (Key::KeyV, 0x0076, 47),
+    (Key::KeyW, 0x0077, 17),
+    (Key::KeyX, 0x0078, 45),
+    (Key::KeyY, 0x0079, 21),
+    (Key::KeyZ, 0x007a, 44),
+    (Key::Key0, 0x0030, 11),
+    (Key::Key1, 0x0031, 2),
+    (Key::Key2, 0x0032, 3),
+    (Key::Key3, 0x0033, 4),
+    (Key::Key4, 0x0034, 5),
+    (Key::Key5, 0x0035, 6),
+    (Key::Key6, 0x0036, 7),
+    (Key::Key7, 0x0037, 8),
+    (Key::Key8, 0x0038, 9),
+    (Key::Key9, 0x0039, 10),
+    // --- 2. Function Keys ---
+    (Key::F1, 0xffbe, 59),
+    (Key::F2, 0xffbf, 60),
+    (Key::F3, 0xffc0, 61),
+    (Key::F4, 0xffc1, 62),
+    (Key::F5, 0xffc2, 63),
+    (Key::F6, 0xffc3, 64),
+    (Key::F7, 0xffc4, 65),
+    (Key::F8, 0xffc5, 66),
+    (Key::F9, 0xffc6, 67),
+    (Key::F10, 0xffc7, 68),
+    (Key::F11, 0xffc8, 87),
+    (Key::F12, 0xffc9, 88),
+    (Key::F13, 0xffca, 183),
+    (Key::F14, 0xffcb, 184),
+    (Key::F15, 0xffcc, 185),
+    (Key::F16, 0xffcd, 186),
+    (Key::F17, 0xffce, 187),
+    (Key::F18, 0xffcf, 188),
+    (Key::F19, 0xffd0, 189),
+    (Key::F20, 0xffd1, 190),
+    (Key::F21, 0xffd2, 191),
+    (Key::F22, 0xffd3, 192),
+    (Key::F23, 0xffd4, 193),
+    (Key::F24, 0xffd5, 194),
+    // --- 3. Punctuation & Symbol Keys ---
+    (Key::Semicolon, 0x003b, 39),
+    (Key::Equal, 0x003d, 13),
+    (Key::Comma, 0x002c, 51),
+    (Key::Minus, 0x002d, 12),
+    (Key::Period, 0x002e, 52),
+    (Key::Slash, 0x002f, 53),
+    (Key::Grave, 0x0060, 41),
+    (Key::LeftBracket, 0x005b, 26),
+    (Key::Backslash, 0x005c, 43),
+    (Key::RightBracket, 0x005d, 27),
+    (Key::Apostrophe, 0x0027, 40),
+    // --- 4. Special Keys ---
+    (Key::Enter, 0xff0d, 28),
+    (Key::Space, 0x0020, 57),
+    (Key::Tab, 0xff09, 15),
+    (Key::Escape, 0xff1b, 1),
+    (Key::Delete, 0xffff, 111),
+    (Key::BackSpace, 0xff08, 14),
+    // --- 5. Navigation & Movement Keys ---
+    (Key::Home, 0xff50, 102),
+    (Key::End, 0xff57, 107),
+    (Key::PageUp, 0xff55, 104),
+    (Key::PageDown, 0xff56, 109),
+    (Key::Insert, 0xff63, 110),
+    (Key::PrintScreen, 0xff61, 99),
+    (Key::ScrollLock, 0xff14, 70),
+    (Key::Pause, 0xff13, 119),
+    (Key::LeftArrow, 0xff51, 105),
+    (Key::RightArrow, 0xff53, 106),
+    (Key::UpArrow, 0xff52, 103),
+    (Key::DownArrow, 0xff54, 108),
+    // --- 6. Numpad Keys ---
+    (Key::Numpad0, 0xffb0, 82),
+    (Key::Numpad1, 0xffb1, 79),
+    (Key::Numpad2, 0xffb2, 80),
+    (Key::Numpad3, 0xffb3, 81),
+    (Key::Numpad4, 0xffb4, 75),
+    (Key::Numpad5, 0xffb5, 76),
+    (Key::Numpad6, 0xffb6, 77),
+    (Key::Numpad7, 0xffb7, 71),
+    (Key::Numpad8, 0xffb8, 72),
+    (Key::Numpad9, 0xffb9, 73),
+    (Key::NumpadAdd, 0xffab, 78),
+    (Key::NumpadSubtract, 0xffad, 74),
+    (Key::NumpadMultiply, 0xffaa, 55),
+    (Key::NumpadDivide, 0xffaf, 98),
+    (Key::NumpadDecimal, 0xffae, 83),
+    // --- 7. Media & Browser Keys (XF86keysym.h) ---
+    (Key::MediaPlayPause, 0x1008ff14, 164),
+    (Key::MediaStop, 0x1008ff15, 166),
+    (Key::MediaNextTrack, 0x1008ff17, 163),
+    (Key::MediaPrevTrack, 0x1008ff16, 165),
+    (Key::VolumeMute, 0x1008ff12, 113),
+    (Key::VolumeDown, 0x1008ff11, 114),
+    (Key::VolumeUp, 0x1008ff13, 115),
+    (Key::BrowserBack, 0x1008ff26, 158),
+    (Key::BrowserForward, 0x1008ff27, 159),
+    (Key::BrowserRefresh, 0x1008ff29, 173),
+    (Key::BrowserStop, 0x1008ff28, 128),
+    (Key::BrowserSearch, 0x1008ff1b, 217),
+    (Key::BrowserFavorites, 0x1008ff30, 156),
+    (Key::BrowserHome, 0x1008ff18, 172),
+    // --- 8. Lock & System Keys ---
+    (Key::ContextMenu, 0xff67, 127),
+    (Key::CapsLock, 0xffe5, 58),
+    (Key::NumLock, 0xff7f, 69),
+    // --- 9. Side Specific Modifier Keys ---
+    (Key::LeftShift, 0xffe1, 42),
+    (Key::RightShift, 0xffe2, 54),
+    (Key::LeftControl, 0xffe3, 29),
+    (Key::RightControl, 0xffe4, 97),
+    (Key::LeftAlt, 0xffe9, 56),
+    (Key::RightAlt, 0xffea, 100),
+    (Key::LeftWin, 0xffeb, 125),
+    (Key::RightWin, 0xffec, 126),
+    // Keypad enter and ISO left tab come back from some layouts
+    (Key::Enter, 0xff8d, 96),
+    (Key::Tab, 0xfe20, 15),
+    // The first column of a keypad key is its NumLock off keysym, ie KP_End
+    (Key::Numpad0, 0xff9e, 82),
+    (Key::Numpad1, 0xff9c, 79),
+    (Key::Numpad2, 0xff99, 80),
+    (Key::Numpad3, 0xff9b, 81),
+    (Key::Numpad4, 0xff96, 75),
+    (Key::Numpad5, 0xff9d, 76),
+    (Key::Numpad6, 0xff98, 77),
+    (Key::Numpad7, 0xff95, 71),
+    (Key::Numpad8, 0xff97, 72),
+    (Key::Numpad9, 0xff9a, 73),
+    (Key::NumpadDecimal, 0xff9f, 83),
+    // Right Alt is AltGr on most layouts
+    (Key::RightAlt, 0xfe03, 100),
+];
+
+pub fn map_modifier(modifiers: &HotkeyModifiers) -> ModMask {
+    let mut mask = ModMask::from(0u16);
+
+    if modifiers.control {
+        mask |= ModMask::CONTROL;
+    }
+
+    if modifiers.shift {
+        mask |= ModMask::SHIFT;
+    }
+
+    if modifiers.alt {
+        mask |= ModMask::M1;
+    }
+
+    if modifiers.win {
+        mask |= ModMask::M4;
+    }
+
+    mask
+}
+
+// Lock and mouse button bits in the event state are ignored
+pub fn map_modifier_back(state: KeyButMask) -> HotkeyModifiers {
+    HotkeyModifiers {
+        control: state.contains(KeyButMask::CONTROL),
+        shift: state.contains(KeyButMask::SHIFT),
+        alt: state.contains(KeyButMask::MOD1),
+        win: state.contains(KeyButMask::MOD4),
+    }
+}
+
+// Left and right keysyms of a modifier key, the left one is used for injection
+pub fn map_modifier_keysyms(modifier: Modifier) -> [Keysym; 2] {
+    match modifier {
+        Modifier::Control => [0xffe3, 0xffe4], // Control_L, Control_R
+        Modifier::Shift => [0xffe1, 0xffe2],   // Shift_L, Shift_R
+        Modifier::Alt => [0xffe9, 0xffea],     // Alt_L, Alt_R
+        Modifier::Win => [0xffeb, 0xffec],     // Super_L, Super_R
+    }
+}
+
+pub fn map_modifier_evdev(modifier: Modifier) -> u16 {
+    match modifier {
+        Modifier::Control => 29, // KEY_LEFTCTRL
+        Modifier::Shift => 42,   // KEY_LEFTSHIFT
+        Modifier::Alt => 56,     // KEY_LEFTALT
+        Modifier::Win => 125,    // KEY_LEFTMETA
+    }
+}
+
+// Every Key but Raw is in KEY_TABLE
+fn entry(key: Key) -> Option<&'static (Key, Keysym, u16)> {
+    KEY_TABLE.iter().find(|(k, _, _)| *k == key)
+}
+
+// Raw is the keysym itself. Err for a Key missing from KEY_TABLE
+pub fn map_key(key: Key) -> Result<Keysym, String> {
+    match key {
+        Key::Raw(keysym) => Ok(keysym),
+        key => entry(key)
+            .map(|(_, keysym, _)| *keysym)
+            .ok_or_else(|| format!("{} has no X keysym", key.label())),
+    }
+}
+
+// None for Raw, a keysym has no fixed evdev code
+pub fn map_key_evdev(key: Key) -> Option<u16> {
+    entry(key).map(|(_, _, code)| *code)
+}
+
+// Keysyms without a Key come back as Raw
+pub fn map_key_back(keysym: Keysym) -> Key {
+    KEY_TABLE
+        .iter()
+        .find(|(_, k, _)| *k == keysym)
+        .map_or(Key::Raw(keysym), |(key, _, _)| *key)
+}
+
+// Every evdev code the virtual keyboard has to declare up front
+pub fn all_evdev_codes() -> impl Iterator<Item = u16> {
+    let modifiers = [
+        Modifier::Control,
+        Modifier::Shift,
+        Modifier::Alt,
+        Modifier::Win,
+    ];
+    KEY_TABLE
+        .iter()
+        .map(|(_, _, code)| *code)
+        .chain(modifiers.into_iter().map(map_modifier_evdev))
+}
+
+// Server keyboard mapping, keycode -> keysyms of that key
+pub struct KeyboardMapping {
+    pub min_keycode: u8,
+    pub keysyms_per_keycode: u8,
+    pub keysyms: Vec<Keysym>,
+}
+
+impl KeyboardMapping {
+    pub fn load<C: Connection>(conn: &C) -> Result<KeyboardMapping, String> {
+        let setup = conn.setup();
+        let (min_keycode, max_keycode) = (setup.min_keycode, setup.max_keycode);
+        let reply = conn
+            .get_keyboard_mapping(min_keycode, max_keycode - min_keycode + 1)
+            .map_err(|e| format!("Could not read the keyboard mapping: {e}"))?
+            .reply()
+            .map_err(|e| format!("Could not read the keyboard mapping: {e}"))?;
+
+        Ok(KeyboardMapping {
+            min_keycode,
+            keysyms_per_keycode: reply.keysyms_per_keycode,
+            keysyms: reply.keysyms,
+        })
+    }
+
+    // The unshifted keysym, which is what map_key_back expects
+    pub fn keysym(&self, keycode: u8) -> Option<Keysym> {
+        let idx = keycode.checked_sub(self.min_keycode)? as usize;
+        let per = self.keysyms_per_keycode as usize;
+        self.keysyms.get(idx * per).copied().filter(|&k| k != 0)
+    }
+
+    // First keycode that produces the keysym in any column
+    pub fn keycode(&self, keysym: Keysym) -> Option<u8> {
+        let per = self.keysyms_per_keycode as usize;
+        if per == 0 {
+            return None;
+        }
+        let idx = self.keysyms.iter().position(|&k| k == keysym)?;
+        u8::try_from(idx / per + self.min_keycode as usize).ok()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keys_round_trip_through_keysyms() {
+        for key in [Key::KeyP, Key::Key1, Key::F12, Key::Space, Key::PageDown] {
+            assert_eq!(map_key_back(map_key(key).unwrap()), key);
+        }
+        assert_eq!(map_key_back(0x1008ff13), Key::VolumeUp); // XF86AudioRaiseVolume
+        assert_eq!(map_key_back(0xff9c), Key::Numpad1); // KP_End
+        assert_eq!(map_key_back(0x1008ff2c), Key::Raw(0x1008ff2c)); // XF86Eject
+        assert_eq!(map_key(Key::Raw(0x1008ff2c)), Ok(0x1008ff2c));
+    }
+
+    #[test]
+    fn evdev_codes_follow_the_us_layout() {
+        assert_eq!(map_key_evdev(Key::KeyQ), Some(16));
+        assert_eq!(map_key_evdev(Key::KeyT), Some(20));
+        assert_eq!(map_key_evdev(Key::Enter), Some(28));
+        assert_eq!(map_key_evdev(Key::Raw(0x1008ff2c)), None);
+        assert!(all_evdev_codes().any(|code| code == map_modifier_evdev(Modifier::Win)));
+    }
+
+    #[test]
+    fn lock_bits_do_not_change_the_chord() {
+        let state = KeyButMask::CONTROL | KeyButMask::SHIFT | KeyButMask::LOCK | KeyButMask::MOD2;
+        assert_eq!(
+            map_modifier_back(state),
+            HotkeyModifiers {
+                control: true,
+                shift: true,
+                ..Default::default()
+            }
+        );
+    }
+
+    #[test]
+    fn looks_up_keycodes_in_the_mapping() {
+        // Two keysyms per keycode starting at keycode 8: [a A] [b B]
+        let mapping = KeyboardMapping {
+            min_keycode: 8,
+            keysyms_per_keycode: 2,
+            keysyms: vec![0x61, 0x41, 0x62, 0x42],
+        };
+        assert_eq!(mapping.keycode(0x62), Some(9));
+        assert_eq!(mapping.keysym(9), Some(0x62));
+        assert_eq!(mapping.keysym(7), None);
+        assert_eq!(mapping.keysym(10), None);
+    }
+}
@@ -0,0 +1,4 @@
+pub mod context;
+pub mod mapper;
+pub mod receiver;
+pub mod sender;
@@ -0,0 +1,363 @@
+use std::{
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc::{self, Receiver, Sender},
+        Arc,
+    },
+    thread::{self, JoinHandle},
+};
+
+use log::{error, warn};
+use x11rb::{
+    connection::Connection,
+    protocol::{
+        xproto::{
+            ClientMessageEvent, ConnectionExt, CreateWindowAux, EventMask, GrabMode, ModMask,
+            Window, WindowClass,
+        },
+        Event,
+    },
+    rust_connection::RustConnection,
+    COPY_DEPTH_FROM_PARENT, COPY_FROM_PARENT,
+};
+
+use crate::{
+    models::hotkey::KeyboardShortcut,
+    platform::{
+        linux::mapper::hotkey_mapper::{
+            map_key, map_key_back, map_modifier, map_modifier_back, KeyboardMapping, LOCK_MASKS,
+        },
+        register_receiver::{request, HotkeyCommand, HotkeyEvent, HotkeyId, HotkeyTable},
+    },
+};
+
+pub struct HotkeyHandle {
+    conn: Arc<RustConnection>,
+    wakeup_window: Window, // Receives the message that unblocks the listener
+    stopping: Arc<AtomicBool>,
+    commands: Sender<HotkeyCommand>,
+    hotkey_thread_handle: Option<JoinHandle<()>>,
+}
+
+impl HotkeyHandle {
+    pub fn register(&self, shortcut: KeyboardShortcut) -> Result<HotkeyId, String> {
+        request(
+            &self.commands,
+            |reply| HotkeyCommand::Register(shortcut, reply),
+            || self.wake(),
+        )
+    }
+
+    pub fn unregister(&self, id: HotkeyId) -> Result<(), String> {
+        request(
+            &self.commands,
+            |reply| HotkeyCommand::Unregister(id, reply),
+            || self.wake(),
+        )
+    }
+
+    pub fn stop(mut self) {
+        self.stopping.store(true, Ordering::SeqCst);
+        self.wake();
+        if let Some(j) = self.hotkey_thread_handle.take() {
+            let _ = j.join();
+        }
+    }
+
+    fn wake(&self) {
+        let wakeup = ClientMessageEvent::new(32, self.wakeup_window, 0u32, [0u32; 5]);
+        let _ = self
+            .conn
+            .send_event(false, self.wakeup_window, EventMask::NO_EVENT, wakeup);
+        let _ = self.conn.flush();
+    }
+}
+
+// Returns once the activation chord is grabbed, or why it could not be.
+// The activation is reported with ACTIVATION_ID
+pub fn start_hotkey_listener(
+    activation: KeyboardShortcut,
+) -> Result<(HotkeyHandle, Receiver<HotkeyEvent>), String> {
+    let (hk_event_tx, hk_event_rx) = mpsc::channel();
+    let (commands_tx, commands_rx) = mpsc::channel();
+    let (ready_tx, ready_rx) = mpsc::channel();
+    let stopping = Arc::new(AtomicBool::new(false));
+
+    let (conn, root, wakeup_window) = connect()?;
+
+    let thread_conn = conn.clone();
+    let thread_stopping = stopping.clone();
+    let hotkey_thread_handle = thread::spawn(move || {
+        let mut listener = match Listener::new(&thread_conn, root) {
+            Ok(listener) => listener,
+            Err(e) => {
+                let _ = ready_tx.send(Err(e));
+                return;
+            }
+        };
+        if let Err(e) = listener.register(activation) {
+            let _ = ready_tx.send(Err(e));
+            return;
+        }
+        let _ = ready_tx.send(Ok(()));
+
+        if let Err(e) = listener.run(&thread_stopping, commands_rx, hk_event_tx) {
+            error!("hotkey thread error: {e}");
+        }
+        listener.unregister_all();
+    });
+
+    let ready = ready_rx
+        .recv()
+        .unwrap_or_else(|_| Err("hotkey thread died early".into()));
+    if let Err(e) = ready {
+        let _ = hotkey_thread_handle.join();
+        return Err(e);
+    }
+
+    Ok((
+        HotkeyHandle {
+            conn,
+            wakeup_window,
+            stopping,
+            commands: commands_tx,
+            hotkey_thread_handle: Some(hotkey_thread_handle),
+        },
+        hk_event_rx,
+    ))
+}
+
+fn connect() -> Result<(Arc<RustConnection>, Window, Window), String> {
+    let (conn, screen) =
+        x11rb::connect(None).map_err(|e| format!("Could not connect to the X server: {e}"))?;
+    let root = conn.setup().roots[screen].root;
+
+    // Hidden window of our own so the handle has somewhere to send wakeups
+    let window = conn
+        .generate_id()
+        .map_err(|e| format!("Could not allocate a window id: {e}"))?;
+    conn.create_window(
+        COPY_DEPTH_FROM_PARENT,
+        window,
+        root,
+        0,
+        0,
+        1,
+        1,
+        0,
+        WindowClass::INPUT_ONLY,
+        COPY_FROM_PARENT,
+        &CreateWindowAux::new(),
+    )
+    .map_err(|e| format!("Could not create the listener window: {e}"))?;
+
+    Ok((Arc::new(conn), root, window))
+}
+
+struct Listener<'a> {
+    conn: &'a RustConnection,
+    root: Window,
+    mapping: KeyboardMapping,
+    table: HotkeyTable,
+    grabs: HashMap<HotkeyId, (u8, u16)>, // Keycode and modifier mask of each grab
+}
+
+impl<'a> Listener<'a> {
+    fn new(conn: &'a RustConnection, root: Window) -> Result<Listener<'a>, String> {
+        Ok(Listener {
+            conn,
+            root,
+            mapping: KeyboardMapping::load(conn)?,
+            table: HotkeyTable::default(),
+            grabs: HashMap::new(),
+        })
+    }
+
+    // Grab every lock variant, otherwise the chord is dead while NumLock is on
+    fn register(&mut self, shortcut: KeyboardShortcut) -> Result<HotkeyId, String> {
+        // A raw keysym of a named key is stored as that key, the press maps back to it
+        let keysym = map_key(shortcut.key)?;
+        let shortcut = KeyboardShortcut {
+            key: map_key_back(keysym),
+            ..shortcut
+        };
+        let keycode = self
+            .mapping
+            .keycode(keysym)
+            .ok_or_else(|| format!("{shortcut} has no key on this keyboard layout"))?;
+        let modifiers = u16::from(map_modifier(&shortcut.modifier));
+        let id = self.table.insert(shortcut)?;
+
+        for lock in LOCK_MASKS {
+            let grabbed = self
+                .conn
+                .grab_key(
+                    false,
+                    self.root,
+                    ModMask::from(modifiers | lock),
+                    keycode,
+                    GrabMode::ASYNC,
+                    GrabMode::ASYNC,
+                )
+                .map_err(|e| e.to_string())
+                .and_then(|cookie| cookie.check().map_err(|e| e.to_string()));
+            if let Err(e) = grabbed {
+                self.ungrab(keycode, modifiers);
+                let _ = self.table.remove(id);
+                return Err(format!(
+                    "Could not register {shortcut}, another program may already use it ({e})"
+                ));
+            }
+        }
+
+        self.grabs.insert(id, (keycode, modifiers));
+        Ok(id)
+    }
+
+    fn unregister(&mut self, id: HotkeyId) -> Result<(), String> {
+        self.table.remove(id)?;
+        if let Some((keycode, modifiers)) = self.grabs.remove(&id) {
+            self.ungrab(keycode, modifiers);
+        }
+        Ok(())
+    }
+
+    fn unregister_all(&mut self) {
+        for id in self.table.ids() {
+            let _ = self.unregister(id);
+        }
+    }
+
+    fn ungrab(&self, keycode: u8, modifiers: u16) {
+        for lock in LOCK_MASKS {
+            let _ = self
+                .conn
+                .ungrab_key(keycode, self.root, ModMask::from(modifiers | lock));
+        }
+        let _ = self.conn.flush();
+    }
+
+    fn handle_commands(&mut self, commands: &Receiver<HotkeyCommand>) {
+        while let Ok(command) = commands.try_recv() {
+            match command {
+                HotkeyCommand::Register(shortcut, reply) => {
+                    let _ = reply.send(self.register(shortcut));
+                }
+                HotkeyCommand::Unregister(id, reply) => {
+                    let _ = reply.send(self.unregister(id));
+                }
+            }
+        }
+    }
+
+    fn run(
+        &mut self,
+        stopping: &AtomicBool,
+        commands: Receiver<HotkeyCommand>,
+        tx: Sender<HotkeyEvent>,
+    ) -> Result<(), String> {
+        // X repeats a held key as release + press pairs with the same timestamp
+        let mut last_release = None;
+        while !stopping.load(Ordering::SeqCst) {
+            let event = self
+                .conn
+                .wait_for_event()
+                .map_err(|e| format!("Lost the X connection: {e}"))?;
+
+            match event {
+                Event::KeyPress(ev) => {
+                    if last_release == Some((ev.detail, ev.time)) {
+                        continue;
+                    }
+                    let Some(keysym) = self.mapping.keysym(ev.detail) else {
+                        warn!("Keycode {} has no keysym", ev.detail);
+                        continue;
+                    };
+                    let key = map_key_back(keysym);
+                    let shortcut = KeyboardShortcut {
+                        key,
+                        modifier: map_modifier_back(ev.state),
+                    };
+                    let Some(id) = self.table.find(&shortcut) else {
+                        warn!("Received {shortcut} which is not registered");
+                        continue;
+                    };
+                    if tx.send(HotkeyEvent { id, shortcut }).is_err() {
+                        break;
+                    }
+                }
+                Event::KeyRelease(ev) => last_release = Some((ev.detail, ev.time)),
+                // Sent by the handle after it queued a command
+                Event::ClientMessage(_) => self.handle_commands(&commands),
+                _ => {}
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        models::hotkey::{HotkeyModifiers, Key},
+        platform::register_receiver::ACTIVATION_ID,
+    };
+
+    const ACTIVATION: KeyboardShortcut = KeyboardShortcut {
+        modifier: HotkeyModifiers {
+            control: true,
+            shift: true,
+            alt: false,
+            win: false,
+        },
+        key: Key::KeyP,
+    };
+
+    // Needs a real or virtual X server: `xvfb-run cargo test -- --ignored`
+    #[test]
+    #[ignore]
+    fn grabs_and_stops_on_a_headless_server() {
+        let (handle, rx) = start_hotkey_listener(ACTIVATION).expect("no X server");
+        handle.stop();
+        assert!(rx.try_recv().is_err());
+    }
+
+    #[test]
+    #[ignore]
+    fn second_grab_of_the_same_chord_names_it() {
+        let (handle, _rx) = start_hotkey_listener(ACTIVATION).expect("no X server");
+        let err = start_hotkey_listener(ACTIVATION).err();
+        handle.stop();
+        assert!(err.unwrap().contains("Ctrl+Shift+P"));
+    }
+
+    #[test]
+    #[ignore]
+    fn events_are_tagged_with_the_registration_id() {
+        use crate::platform::{
+            key_sender::KeySender, linux::sender::hotkey_sender::XTestKeySender,
+        };
+
+        let (handle, rx) = start_hotkey_listener(ACTIVATION).expect("no X server");
+        let direct = KeyboardShortcut {
+            key: Key::F9,
+            ..ACTIVATION
+        };
+        let direct_id = handle.register(direct).unwrap();
+
+        let mut sender = XTestKeySender::connect().expect("no X server");
+        sender.send_shortcut(&ACTIVATION).unwrap();
+        sender.send_shortcut(&direct).unwrap();
+
+        let timeout = std::time::Duration::from_secs(2);
+        let first = rx.recv_timeout(timeout).map(|ev| ev.id);
+        let second = rx.recv_timeout(timeout).map(|ev| ev.id);
+        handle.unregister(direct_id).unwrap();
+        handle.stop();
+        assert_eq!(first, Ok(ACTIVATION_ID));
+        assert_eq!(second, Ok(direct_id));
+    }
+}
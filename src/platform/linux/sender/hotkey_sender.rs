@@ -0,0 +1,115 @@
+// Key injection for X11 sessions through the XTest extension. The server treats
+// the events like real key presses, so they reach whichever window has focus.
+
+use x11rb::{
+    connection::{Connection, RequestConnection},
+    protocol::{
+        xproto::{ConnectionExt, Keysym, Window, KEY_PRESS_EVENT, KEY_RELEASE_EVENT},
+        xtest::{self, ConnectionExt as XTestConnectionExt},
+    },
+    rust_connection::RustConnection,
+    CURRENT_TIME,
+};
+
+use crate::{
+    models::hotkey::{HotkeyModifiers, Modifier},
+    platform::{
+        key_sender::{KeyCode, KeyEvent, KeySender},
+        linux::mapper::hotkey_mapper::{map_key, map_modifier_keysyms, KeyboardMapping},
+    },
+};
+
+pub struct XTestKeySender {
+    conn: RustConnection,
+    root: Window,
+    mapping: KeyboardMapping,
+}
+
+impl XTestKeySender {
+    // Fails when there is no X server or it lacks XTest
+    pub fn connect() -> Result<XTestKeySender, String> {
+        let (conn, screen) =
+            x11rb::connect(None).map_err(|e| format!("Could not connect to the X server: {e}"))?;
+        let root = conn.setup().roots[screen].root;
+
+        let has_xtest = conn
+            .extension_information(xtest::X11_EXTENSION_NAME)
+            .map_err(|e| format!("Could not query X extensions: {e}"))?
+            .is_some();
+        if !has_xtest {
+            return Err("The X server has no XTest extension".into());
+        }
+
+        let mapping = KeyboardMapping::load(&conn)?;
+        Ok(XTestKeySender {
+            conn,
+            root,
+            mapping,
+        })
+    }
+
+    fn keysym(code: KeyCode) -> Result<Keysym, String> {
+        match code {
+            KeyCode::Modifier(modifier) => Ok(map_modifier_keysyms(modifier)[0]),
+            KeyCode::Key(key) => map_key(key),
+        }
+    }
+
+    fn keycode(&self, code: KeyCode) -> Result<u8, String> {
+        self.mapping
+            .keycode(Self::keysym(code)?)
+            .ok_or_else(|| format!("{code:?} has no key on this keyboard layout"))
+    }
+}
+
+impl KeySender for XTestKeySender {
+    fn send(&mut self, events: &[KeyEvent]) -> Result<(), String> {
+        // Resolve everything first so a bad key does not leave modifiers held
+        let mut resolved = Vec::with_capacity(events.len());
+        for event in events {
+            resolved.push(match event {
+                KeyEvent::Down(code) => (KEY_PRESS_EVENT, self.keycode(*code)?),
+                KeyEvent::Up(code) => (KEY_RELEASE_EVENT, self.keycode(*code)?),
+            });
+        }
+
+        for (type_, keycode) in resolved {
+            self.conn
+                .xtest_fake_input(type_, keycode, CURRENT_TIME, self.root, 0, 0, 0)
+                .map_err(|e| format!("XTest fake input failed: {e}"))?;
+        }
+
+        // Round trip so the events are processed before we return
+        self.conn
+            .get_input_focus()
+            .map_err(|e| format!("XTest fake input failed: {e}"))?
+            .reply()
+            .map_err(|e| format!("XTest fake input failed: {e}"))?;
+        Ok(())
+    }
+
+    fn held_modifiers(&self) -> HotkeyModifiers {
+        let Some(keymap) = self
+            .conn
+            .query_keymap()
+            .ok()
+            .and_then(|cookie| cookie.reply().ok())
+        else {
+            return HotkeyModifiers::default();
+        };
+
+        let is_down = |keysym: Keysym| {
+            self.mapping
+                .keycode(keysym)
+                .is_some_and(|kc| keymap.keys[kc as usize / 8] & (1 << (kc % 8)) != 0)
+        };
+        let held = |modifier: Modifier| map_modifier_keysyms(modifier).into_iter().any(is_down);
+
+        HotkeyModifiers {
+            control: held(Modifier::Control),
+            shift: held(Modifier::Shift),
+            alt: held(Modifier::Alt),
+            win: held(Modifier::Win),
+        }
+    }
+}
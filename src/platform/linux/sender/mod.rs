@@ -0,0 +1,2 @@
+pub mod hotkey_sender;
+pub mod uinput_sender;
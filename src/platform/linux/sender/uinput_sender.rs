@@ -0,0 +1,182 @@
+// Key injection through a /dev/uinput virtual keyboard. This sits below the
+// display server, so it also works on Wayland where XTest only reaches XWayland
+// windows. Needs write access to /dev/uinput (usually the `input` group).
+
+use std::{
+    fs::{File, OpenOptions},
+    io::Write,
+    mem,
+    os::{fd::AsRawFd, unix::fs::OpenOptionsExt},
+    slice, thread,
+    time::Duration,
+};
+
+use crate::platform::{
+    key_sender::{KeyCode, KeyEvent, KeySender},
+    linux::mapper::hotkey_mapper::{
+        all_evdev_codes, map_key, map_key_back, map_key_evdev, map_modifier_evdev,
+    },
+};
+
+const UINPUT_PATH: &str = "/dev/uinput";
+
+// linux/uinput.h
+const UI_SET_EVBIT: libc::c_ulong = 0x4004_5564;
+const UI_SET_KEYBIT: libc::c_ulong = 0x4004_5565;
+const UI_DEV_SETUP: libc::c_ulong = 0x405c_5503;
+const UI_DEV_CREATE: libc::c_ulong = 0x5501;
+const UI_DEV_DESTROY: libc::c_ulong = 0x5502;
+
+// linux/input-event-codes.h
+const EV_SYN: u16 = 0x00;
+const EV_KEY: u16 = 0x01;
+const SYN_REPORT: u16 = 0;
+const BUS_VIRTUAL: u16 = 0x06;
+
+const DEVICE_NAME: &[u8] = b"global_palette virtual keyboard";
+
+// The desktop needs a moment to pick up a new input device before it listens
+const DEVICE_SETTLE: Duration = Duration::from_millis(200);
+
+pub struct UinputKeySender {
+    device: File,
+}
+
+impl UinputKeySender {
+    pub fn open() -> Result<UinputKeySender, String> {
+        let device = OpenOptions::new()
+            .write(true)
+            .custom_flags(libc::O_NONBLOCK)
+            .open(UINPUT_PATH)
+            .map_err(|e| format!("Could not open {UINPUT_PATH}: {e}"))?;
+        let fd = device.as_raw_fd();
+
+        let mut setup: libc::uinput_setup = unsafe { mem::zeroed() };
+        setup.id.bustype = BUS_VIRTUAL;
+        for (dst, src) in setup.name.iter_mut().zip(DEVICE_NAME) {
+            *dst = *src as libc::c_char;
+        }
+
+        unsafe {
+            ioctl(fd, UI_SET_EVBIT, EV_KEY as libc::c_ulong)?;
+            for code in all_evdev_codes() {
+                ioctl(fd, UI_SET_KEYBIT, code as libc::c_ulong)?;
+            }
+            ioctl(fd, UI_DEV_SETUP, &setup as *const _ as libc::c_ulong)?;
+            ioctl(fd, UI_DEV_CREATE, 0)?;
+        }
+
+        thread::sleep(DEVICE_SETTLE);
+        Ok(UinputKeySender { device })
+    }
+}
+
+unsafe fn ioctl(fd: libc::c_int, request: libc::c_ulong, arg: libc::c_ulong) -> Result<(), String> {
+    if libc::ioctl(fd, request as _, arg) < 0 {
+        return Err(format!(
+            "uinput ioctl {request:#x} failed: {}",
+            std::io::Error::last_os_error()
+        ));
+    }
+    Ok(())
+}
+
+// (type, code, value) triples, each key change followed by a sync report. Raw
+// keys are X keysyms, which have no evdev code
+pub fn input_events(events: &[KeyEvent]) -> Result<Vec<(u16, u16, i32)>, String> {
+    let mut out = Vec::with_capacity(events.len() * 2);
+    for event in events {
+        let (code, value) = match event {
+            KeyEvent::Down(code) => (code, 1),
+            KeyEvent::Up(code) => (code, 0),
+        };
+        let code = match code {
+            KeyCode::Modifier(modifier) => map_modifier_evdev(*modifier),
+            // A Raw keysym of a named key, ie Raw(0x74) for t, still has a code
+            KeyCode::Key(key) => map_key_evdev(map_key_back(map_key(*key)?))
+                .ok_or_else(|| format!("{} cannot be sent through uinput", key.label()))?,
+        };
+        out.push((EV_KEY, code, value));
+        out.push((EV_SYN, SYN_REPORT, 0));
+    }
+    Ok(out)
+}
+
+impl KeySender for UinputKeySender {
+    fn send(&mut self, events: &[KeyEvent]) -> Result<(), String> {
+        let raw: Vec<libc::input_event> = input_events(events)?
+            .into_iter()
+            .map(|(type_, code, value)| {
+                let mut event: libc::input_event = unsafe { mem::zeroed() };
+                event.type_ = type_;
+                event.code = code;
+                event.value = value;
+                event
+            })
+            .collect();
+
+        let bytes = unsafe {
+            slice::from_raw_parts(
+                raw.as_ptr() as *const u8,
+                raw.len() * mem::size_of::<libc::input_event>(),
+            )
+        };
+        self.device
+            .write_all(bytes)
+            .map_err(|e| format!("Could not write to {UINPUT_PATH}: {e}"))
+    }
+}
+
+impl Drop for UinputKeySender {
+    fn drop(&mut self) {
+        unsafe {
+            let _ = ioctl(self.device.as_raw_fd(), UI_DEV_DESTROY, 0);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::hotkey::{Key, Modifier};
+
+    #[test]
+    fn every_key_change_is_followed_by_a_sync() {
+        let events = [
+            KeyEvent::Down(KeyCode::Modifier(Modifier::Control)),
+            KeyEvent::Down(KeyCode::Key(Key::KeyT)),
+            KeyEvent::Up(KeyCode::Key(Key::KeyT)),
+            KeyEvent::Up(KeyCode::Modifier(Modifier::Control)),
+        ];
+        assert_eq!(
+            input_events(&events).unwrap(),
+            vec![
+                (EV_KEY, 29, 1),
+                (EV_SYN, SYN_REPORT, 0),
+                (EV_KEY, 20, 1),
+                (EV_SYN, SYN_REPORT, 0),
+                (EV_KEY, 20, 0),
+                (EV_SYN, SYN_REPORT, 0),
+                (EV_KEY, 29, 0),
+                (EV_SYN, SYN_REPORT, 0),
+            ]
+        );
+    }
+
+    #[test]
+    fn raw_keysym_of_a_named_key_is_sent_as_that_key() {
+        let events = [KeyEvent::Down(KeyCode::Key(Key::Raw(0x74)))];
+        assert_eq!(
+            input_events(&events).unwrap(),
+            vec![(EV_KEY, 20, 1), (EV_SYN, SYN_REPORT, 0)]
+        );
+        assert!(input_events(&[KeyEvent::Down(KeyCode::Key(Key::Raw(0x20ac)))]).is_err());
+    }
+
+    #[test]
+    fn setup_request_matches_the_struct_size() {
+        // _IOW('U', 3, struct uinput_setup)
+        let size = mem::size_of::<libc::uinput_setup>() as libc::c_ulong;
+        assert_eq!(UI_DEV_SETUP, 0x4000_0000 | (size << 16) | (0x55 << 8) | 3);
+    }
+}
@@ -1,6 +1,12 @@
+pub mod clipboard;
 pub mod hotkey_actions;
+pub mod key_sender;
 pub mod platform_interface;
 pub mod register_receiver;
+pub mod spawner;
 
 #[cfg(target_os = "windows")]
 mod windows;
+
+#[cfg(target_os = "linux")]
+mod linux;
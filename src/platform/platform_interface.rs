@@ -1,19 +1,23 @@
-use crate::{
-    models::action::{AppProcessName, ContextRoot},
-    platform::windows as platwins,
-};
+use crate::models::action::{AppProcessName, ContextRoot};
+#[cfg(target_os = "linux")]
+use crate::platform::linux as platlinux;
+#[cfg(target_os = "windows")]
+use crate::platform::windows as platwins;
 use cfg_if::cfg_if;
 use log::error;
 use raw_window_handle::RawWindowHandle;
+use std::path::PathBuf;
 
 pub fn get_all_context() -> ContextRoot {
     cfg_if! {
         if #[cfg(target_os = "windows")] {
             // Destructure the tuple returned by your Windows function
             let (fg, bg) = platwins::context::context::get_all_windows();
+        } else if #[cfg(target_os = "linux")] {
+            let (fg, bg) = platlinux::context::context::get_all_windows();
         } else {
             // Fallback for other OSs
-            panic!("Not valid os")
+            error!("Context discovery is not supported on this os");
             let fg = vec![];
             let bg = vec![];
         }
@@ -25,20 +29,135 @@ pub fn get_all_context() -> ContextRoot {
     }
 }
 
+// Window the user is working in, captured before the palette takes focus
+pub fn get_foreground_context() -> Option<RawWindowHandle> {
+    cfg_if! {
+        if #[cfg(target_os = "windows")] {
+            platwins::context::context::get_foreground_window()
+        } else if #[cfg(target_os = "linux")] {
+            platlinux::context::context::get_foreground_window()
+        } else {
+            None
+        }
+    }
+}
+
+// Give focus back to a window. Returns false if the OS refused
+pub fn focus_context(context: &RawWindowHandle) -> bool {
+    match context {
+        #[cfg(target_os = "windows")]
+        RawWindowHandle::Win32(_) => {
+            use platwins::context::context as plat_win_ctx;
+            plat_win_ctx::get_hwnd_from_raw(*context)
+                .map(|hwnd| plat_win_ctx::focus_window(&hwnd))
+                .unwrap_or(false)
+        }
+        #[cfg(target_os = "linux")]
+        RawWindowHandle::Xlib(_) | RawWindowHandle::Xcb(_) => {
+            use platlinux::context::context as plat_linux_ctx;
+            plat_linux_ctx::get_window_from_raw(*context)
+                .map(plat_linux_ctx::focus_window)
+                .unwrap_or(false)
+        }
+        _ => {
+            error!("Focusing {context:?} is not supported on this os");
+            false
+        }
+    }
+}
+
+// Topmost window of a process. False when it has no window or the OS refused
+pub fn focus_process(process_name: &str) -> bool {
+    let context = get_all_context();
+    context
+        .fg_context
+        .iter()
+        .chain(&context.bg_context)
+        .find(|c| {
+            c.get_app_process_name()
+                .is_some_and(|name| name == process_name)
+        })
+        .is_some_and(focus_context)
+}
+
+// Install wide extension folder, ie where a team pack is deployed
+pub fn system_extensions_dir() -> Option<PathBuf> {
+    cfg_if! {
+        if #[cfg(target_os = "windows")] {
+            let data = std::env::var_os("ProgramData").map(PathBuf::from);
+        } else if #[cfg(target_os = "macos")] {
+            let data = Some(PathBuf::from("/Library/Application Support"));
+        } else {
+            let data = Some(PathBuf::from("/usr/share"));
+        }
+    }
+    data.map(|d| d.join("global_palette").join("extensions"))
+}
+
+// Per user extension folder, for personal extensions
+pub fn user_extensions_dir() -> Option<PathBuf> {
+    cfg_if! {
+        if #[cfg(target_os = "windows")] {
+            let config = std::env::var_os("APPDATA").map(PathBuf::from);
+        } else if #[cfg(target_os = "macos")] {
+            let config = std::env::var_os("HOME")
+                .map(|h| PathBuf::from(h).join("Library").join("Application Support"));
+        } else {
+            let config = std::env::var_os("XDG_CONFIG_HOME")
+                .filter(|d| !d.is_empty())
+                .map(PathBuf::from)
+                .or_else(|| std::env::var_os("HOME").map(|h| PathBuf::from(h).join(".config")));
+        }
+    }
+    config.map(|c| c.join("global_palette").join("extensions"))
+}
+
 pub trait RawWindowHandleExt {
     fn get_app_process_name(&self) -> Option<AppProcessName>;
-    // fn get_window_name
+    // None when the window has no title
+    fn get_window_title(&self) -> Option<String>;
 }
 
 impl RawWindowHandleExt for RawWindowHandle {
     fn get_app_process_name(&self) -> Option<AppProcessName> {
         match self {
+            #[cfg(target_os = "windows")]
             RawWindowHandle::Win32(_) => {
                 use platwins::context::context as plat_win_ctx;
                 let hwnd = plat_win_ctx::get_hwnd_from_raw(*self)?;
                 plat_win_ctx::get_app_process_name(&hwnd)
             }
-            _ => todo!("This os is not supported"),
+            #[cfg(target_os = "linux")]
+            RawWindowHandle::Xlib(_) | RawWindowHandle::Xcb(_) => {
+                use platlinux::context::context as plat_linux_ctx;
+                let window = plat_linux_ctx::get_window_from_raw(*self)?;
+                plat_linux_ctx::get_app_process_name(window)
+            }
+            _ => {
+                error!("Process lookup for {self:?} is not supported on this os");
+                None
+            }
+        }
+    }
+
+    fn get_window_title(&self) -> Option<String> {
+        match self {
+            #[cfg(target_os = "windows")]
+            RawWindowHandle::Win32(_) => {
+                use platwins::context::context as plat_win_ctx;
+                let hwnd = plat_win_ctx::get_hwnd_from_raw(*self)?;
+                plat_win_ctx::get_window_title(&hwnd)
+            }
+            #[cfg(target_os = "linux")]
+            RawWindowHandle::Xlib(_) | RawWindowHandle::Xcb(_) => {
+                use platlinux::context::context as plat_linux_ctx;
+                let window = plat_linux_ctx::get_window_from_raw(*self)?;
+                plat_linux_ctx::get_window_title(window)
+            }
+            _ => {
+                error!("Window title of {self:?} is not supported on this os");
+                None
+            }
         }
     }
 }
@@ -1 +1,167 @@
+// Platform neutral side of the hotkey listener. Each OS listener owns a thread
+// that talks to the OS, the handle sends it HotkeyCommands and gets replies back.
 
+use std::{
+    collections::HashMap,
+    sync::mpsc::{self, Sender},
+};
+
+use crate::models::hotkey::KeyboardShortcut;
+
+pub type HotkeyId = u32;
+
+// The activation chord is always the first registration
+pub const ACTIVATION_ID: HotkeyId = 1;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HotkeyEvent {
+    pub id: HotkeyId,
+    pub shortcut: KeyboardShortcut,
+}
+
+pub enum HotkeyCommand {
+    Register(KeyboardShortcut, Sender<Result<HotkeyId, String>>),
+    Unregister(HotkeyId, Sender<Result<(), String>>),
+}
+
+// Send a command to the listener thread and block until it answers.
+// `wake` interrupts the thread's blocking wait on the OS.
+pub fn request<T>(
+    commands: &Sender<HotkeyCommand>,
+    command: impl FnOnce(Sender<Result<T, String>>) -> HotkeyCommand,
+    wake: impl FnOnce(),
+) -> Result<T, String> {
+    let (reply_tx, reply_rx) = mpsc::channel();
+    commands
+        .send(command(reply_tx))
+        .map_err(|_| "The hotkey listener has stopped".to_string())?;
+    wake();
+    reply_rx
+        .recv()
+        .map_err(|_| "The hotkey listener has stopped".to_string())?
+}
+
+// Registered hotkeys, owned by the listener thread
+#[derive(Debug)]
+pub struct HotkeyTable {
+    next_id: HotkeyId,
+    hotkeys: HashMap<HotkeyId, KeyboardShortcut>,
+}
+
+impl Default for HotkeyTable {
+    fn default() -> Self {
+        HotkeyTable {
+            next_id: ACTIVATION_ID,
+            hotkeys: HashMap::new(),
+        }
+    }
+}
+
+impl HotkeyTable {
+    // The OS would deliver a chord to only one of two registrations
+    pub fn insert(&mut self, shortcut: KeyboardShortcut) -> Result<HotkeyId, String> {
+        if self.find(&shortcut).is_some() {
+            return Err(format!("{shortcut} is already registered"));
+        }
+        let id = self.next_id;
+        self.next_id += 1;
+        self.hotkeys.insert(id, shortcut);
+        Ok(id)
+    }
+
+    pub fn remove(&mut self, id: HotkeyId) -> Result<KeyboardShortcut, String> {
+        self.hotkeys
+            .remove(&id)
+            .ok_or_else(|| format!("No hotkey registered with id {id}"))
+    }
+
+    pub fn find(&self, shortcut: &KeyboardShortcut) -> Option<HotkeyId> {
+        self.hotkeys
+            .iter()
+            .find(|(_, registered)| *registered == shortcut)
+            .map(|(id, _)| *id)
+    }
+
+    pub fn ids(&self) -> Vec<HotkeyId> {
+        self.hotkeys.keys().copied().collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::hotkey::{HotkeyModifiers, Key};
+
+    fn chord(key: Key) -> KeyboardShortcut {
+        KeyboardShortcut {
+            modifier: HotkeyModifiers {
+                control: true,
+                ..Default::default()
+            },
+            key,
+        }
+    }
+
+    #[test]
+    fn first_registration_is_the_activation() {
+        let mut table = HotkeyTable::default();
+        assert_eq!(table.insert(chord(Key::KeyP)), Ok(ACTIVATION_ID));
+        assert_eq!(table.insert(chord(Key::KeyT)), Ok(ACTIVATION_ID + 1));
+    }
+
+    #[test]
+    fn same_chord_cannot_be_registered_twice() {
+        let mut table = HotkeyTable::default();
+        table.insert(chord(Key::KeyT)).unwrap();
+        assert!(table.insert(chord(Key::KeyT)).is_err());
+    }
+
+    #[test]
+    fn ids_are_not_reused_after_unregister() {
+        let mut table = HotkeyTable::default();
+        let id = table.insert(chord(Key::KeyT)).unwrap();
+        assert_eq!(table.remove(id), Ok(chord(Key::KeyT)));
+        assert!(table.remove(id).is_err());
+        assert_eq!(table.find(&chord(Key::KeyT)), None);
+        assert_eq!(table.insert(chord(Key::KeyT)), Ok(id + 1));
+    }
+
+    #[test]
+    fn commands_are_answered_by_the_listener_thread() {
+        let (commands_tx, commands_rx) = mpsc::channel();
+        let listener = std::thread::spawn(move || {
+            let mut table = HotkeyTable::default();
+            while let Ok(command) = commands_rx.recv() {
+                match command {
+                    HotkeyCommand::Register(shortcut, reply) => {
+                        let _ = reply.send(table.insert(shortcut));
+                    }
+                    HotkeyCommand::Unregister(id, reply) => {
+                        let _ = reply.send(table.remove(id).map(|_| ()));
+                    }
+                }
+            }
+        });
+
+        let register = |shortcut| {
+            request(
+                &commands_tx,
+                |reply| HotkeyCommand::Register(shortcut, reply),
+                || {},
+            )
+        };
+        let id = register(chord(Key::KeyT)).unwrap();
+        assert!(register(chord(Key::KeyT)).is_err());
+        assert_eq!(
+            request(
+                &commands_tx,
+                |reply| HotkeyCommand::Unregister(id, reply),
+                || {}
+            ),
+            Ok(())
+        );
+
+        drop(commands_tx);
+        listener.join().unwrap();
+    }
+}
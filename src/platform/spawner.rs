@@ -0,0 +1,187 @@
+// Starts programs, opens files and URLs, runs shell commands. Behind a trait so
+// the executor can be tested without starting anything
+
+#[cfg(test)]
+use std::sync::{Arc, Mutex};
+use std::{
+    process::{Command, Stdio},
+    thread,
+};
+
+use cfg_if::cfg_if;
+use log::{info, warn};
+
+use crate::models::action::{Os, Spawn};
+
+pub trait Spawner {
+    // Returns once the process is started, it is not waited for
+    fn spawn(&mut self, spawn: &Spawn) -> Result<(), String>;
+}
+
+pub struct OsSpawner {
+    os: Os,
+}
+
+impl OsSpawner {
+    pub fn new(os: Os) -> OsSpawner {
+        OsSpawner { os }
+    }
+}
+
+impl Spawner for OsSpawner {
+    fn spawn(&mut self, spawn: &Spawn) -> Result<(), String> {
+        let mut child = command(spawn, self.os)
+            .stdin(Stdio::null())
+            .spawn()
+            .map_err(|e| format!("Could not {spawn}: {e}"))?;
+
+        // Reaped on its own thread so finished processes do not linger. explorer
+        // exits with 1 even when it opened the target
+        let explorer = matches!(spawn, Spawn::Open(_)) && self.os == Os::Windows;
+        let spawn = spawn.clone();
+        thread::spawn(move || match child.wait() {
+            Ok(status) if status.success() || explorer => info!("{spawn} finished"),
+            Ok(status) => warn!("{spawn} exited with {status}"),
+            Err(e) => warn!("{spawn}: {e}"),
+        });
+        Ok(())
+    }
+}
+
+// Opening goes through the desktop's default handler, shell commands through
+// the OS shell. A launch runs the program directly, its args are not parsed
+pub fn command(spawn: &Spawn, os: Os) -> Command {
+    match spawn {
+        Spawn::Launch(launch) => {
+            let mut command = Command::new(&launch.program);
+            command.args(&launch.args).envs(&launch.env);
+            if let Some(cwd) = &launch.cwd {
+                command.current_dir(cwd);
+            }
+            command
+        }
+        // The target is one argument of the opener, never parsed by a shell
+        Spawn::Open(target) => {
+            let opener = match os {
+                Os::Windows => "explorer",
+                Os::Mac => "open",
+                Os::Linux => "xdg-open",
+            };
+            let mut command = Command::new(opener);
+            command.arg(target);
+            command
+        }
+        Spawn::Shell(line) => match os {
+            Os::Windows => cmd(line),
+            Os::Mac | Os::Linux => {
+                let mut command = Command::new("sh");
+                command.args(["-c", line]);
+                command
+            }
+        },
+    }
+}
+
+// cmd parses its command line itself, so the line is passed as is. The quoting
+// Command::args does for MSVC programs would escape the quotes in it
+fn cmd(line: &str) -> Command {
+    let mut command = Command::new("cmd");
+    cfg_if! {
+        if #[cfg(target_os = "windows")] {
+            use std::os::windows::process::CommandExt;
+            command.raw_arg(format!("/C {line}"));
+        } else {
+            command.args(["/C", line]);
+        }
+    }
+    command
+}
+
+// In-memory backend for tests. Clones share the log, like RecordingKeySender
+#[cfg(test)]
+#[derive(Debug, Default, Clone)]
+pub struct RecordingSpawner {
+    spawned: Arc<Mutex<Vec<Spawn>>>,
+}
+
+#[cfg(test)]
+impl RecordingSpawner {
+    pub fn spawned(&self) -> Vec<Spawn> {
+        self.spawned.lock().unwrap().clone()
+    }
+}
+
+#[cfg(test)]
+impl Spawner for RecordingSpawner {
+    fn spawn(&mut self, spawn: &Spawn) -> Result<(), String> {
+        self.spawned.lock().unwrap().push(spawn.clone());
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::config::Launch;
+    use std::ffi::OsStr;
+
+    fn parts(command: &Command) -> Vec<&OsStr> {
+        std::iter::once(command.get_program())
+            .chain(command.get_args())
+            .collect()
+    }
+
+    // A raw cmd line is one argument on Windows, two elsewhere
+    fn line(command: &Command) -> String {
+        parts(command)
+            .iter()
+            .map(|part| part.to_string_lossy())
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    #[test]
+    fn builds_the_command_for_each_os() {
+        let launch = Spawn::Launch(Launch {
+            program: "code".into(),
+            args: vec!["--new-window".into()],
+            cwd: Some("/src".into()),
+            env: [("RUST_LOG".to_string(), "info".to_string())].into(),
+        });
+        let launched = command(&launch, Os::Linux);
+        assert_eq!(parts(&launched), ["code", "--new-window"]);
+        assert_eq!(launched.get_current_dir(), Some("/src".as_ref()));
+        assert_eq!(
+            launched.get_envs().collect::<Vec<_>>(),
+            [("RUST_LOG".as_ref(), Some("info".as_ref()))]
+        );
+
+        let url = Spawn::Open("https://github.com".into());
+        assert_eq!(
+            parts(&command(&url, Os::Mac)),
+            ["open", "https://github.com"]
+        );
+        assert_eq!(parts(&command(&url, Os::Linux))[0], "xdg-open");
+        assert_eq!(
+            parts(&command(&url, Os::Windows)),
+            ["explorer", "https://github.com"]
+        );
+
+        // Would end the quoted target and start calc if it went through cmd
+        let injected = Spawn::Open(r#"https://x.org/" & calc & ""#.into());
+        assert_eq!(
+            parts(&command(&injected, Os::Windows)),
+            ["explorer", r#"https://x.org/" & calc & ""#]
+        );
+
+        let shell = Spawn::Shell(r#"echo "hi there" > out.txt"#.into());
+        assert_eq!(
+            line(&command(&shell, Os::Windows)),
+            r#"cmd /C echo "hi there" > out.txt"#
+        );
+        assert_eq!(
+            parts(&command(&shell, Os::Linux)),
+            ["sh", "-c", r#"echo "hi there" > out.txt"#]
+        );
+    }
+}
@@ -12,7 +12,7 @@ use windows::Win32::System::Threading::{OpenProcess, PROCESS_QUERY_INFORMATION,
 use windows::Win32::UI::Input::KeyboardAndMouse::GetActiveWindow;
 use windows::Win32::UI::WindowsAndMessaging::{
     EnumWindows, GetForegroundWindow, GetWindowTextW, GetWindowThreadProcessId, IsWindow,
-    IsWindowVisible,
+    IsWindowVisible, SetForegroundWindow,
 };
 
 pub fn get_foreground_window_handle() -> HWND {
@@ -20,6 +20,17 @@ pub fn get_foreground_window_handle() -> HWND {
     unsafe { GetForegroundWindow() }
 }
 
+pub fn get_foreground_window() -> Option<RawWindowHandle> {
+    let hwnd = get_foreground_window_handle();
+    NonZeroIsize::new(hwnd.0 as isize).map(|h| RawWindowHandle::Win32(Win32WindowHandle::new(h)))
+}
+
+// Windows only lets the foreground process move focus. This works because the
+// palette still owns the foreground when it hands focus back.
+pub fn focus_window(hwnd: &HWND) -> bool {
+    unsafe { SetForegroundWindow(*hwnd).as_bool() }
+}
+
 struct WindowEnumContext {
     fg: Vec<RawWindowHandle>,
     bg: Vec<RawWindowHandle>,
@@ -81,7 +92,7 @@ pub fn get_all_windows() -> (Vec<RawWindowHandle>, Vec<RawWindowHandle>) {
     // (context.fg, context.bg)
 }
 
-fn get_window_title(hwnd: &HWND) -> String {
+pub fn get_window_title(hwnd: &HWND) -> Option<String> {
     let mut title_buffer: [u16; 512] = [0; 512]; // Buffer for the title text
     let length = unsafe {
         // GetWindowTextW returns the length of the string copied
@@ -90,11 +101,13 @@ fn get_window_title(hwnd: &HWND) -> String {
 
     if length > 0 {
         // Convert the UTF-16 buffer slice into a Rust OsString, then String
-        OsString::from_wide(&title_buffer[0..length as usize])
-            .to_string_lossy()
-            .into_owned()
+        Some(
+            OsString::from_wide(&title_buffer[0..length as usize])
+                .to_string_lossy()
+                .into_owned(),
+        )
     } else {
-        String::from("[No Title]")
+        None
     }
 }
 
@@ -147,7 +160,7 @@ pub fn print_window_context(handles: &Vec<HWND>) {
     // Retrieve the active window handle
     for handle in handles {
         if !handle.is_invalid() {
-            let title = get_window_title(&handle);
+            let title = get_window_title(&handle).unwrap_or_else(|| "[No Title]".into());
             let app_name = get_app_process_name(&handle);
 
             println!("--- Window Context ---");
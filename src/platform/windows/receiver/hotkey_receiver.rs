@@ -7,74 +7,136 @@ use std::{
 
 use crate::{
     models::hotkey::KeyboardShortcut,
-    platform::windows::{
-        mapper::hotkey_mapper::{map_key_back, map_modifier_back},
-        shortcuts,
+    platform::{
+        register_receiver::{request, HotkeyCommand, HotkeyEvent, HotkeyId, HotkeyTable},
+        windows::mapper::hotkey_mapper::{map_key, map_key_back, map_modifier, map_modifier_back},
     },
 };
 
-use log::warn;
-use windows::{
-    core::Result,
-    Win32::{
-        System::Threading::GetCurrentThreadId,
-        UI::{
-            Input::KeyboardAndMouse::{
-                RegisterHotKey, UnregisterHotKey, HOT_KEY_MODIFIERS, MOD_CONTROL, MOD_NOREPEAT,
-                MOD_SHIFT, VIRTUAL_KEY, VK_P, VK_SPACE,
-            },
-            WindowsAndMessaging::{
-                GetMessageW, PeekMessageW, PostThreadMessageW, MSG, PM_NOREMOVE, WM_HOTKEY, WM_QUIT,
-            },
+use windows::Win32::{
+    System::Threading::GetCurrentThreadId,
+    UI::{
+        Input::KeyboardAndMouse::{
+            RegisterHotKey, UnregisterHotKey, HOT_KEY_MODIFIERS, MOD_NOREPEAT, VIRTUAL_KEY,
+        },
+        WindowsAndMessaging::{
+            GetMessageW, PeekMessageW, PostThreadMessageW, MSG, PM_NOREMOVE, WM_APP, WM_HOTKEY,
+            WM_QUIT,
         },
     },
 };
 
+// Posted to the listener thread after a command was queued
+const WM_HOTKEY_COMMAND: u32 = WM_APP + 1;
+
 pub struct HotkeyHandle {
     thread_id: u32,
+    commands: Sender<HotkeyCommand>,
     hotkey_thread_handle: Option<JoinHandle<()>>,
 }
 
 impl HotkeyHandle {
+    pub fn register(&self, shortcut: KeyboardShortcut) -> Result<HotkeyId, String> {
+        request(
+            &self.commands,
+            |reply| HotkeyCommand::Register(shortcut, reply),
+            || self.post(WM_HOTKEY_COMMAND),
+        )
+    }
+
+    pub fn unregister(&self, id: HotkeyId) -> Result<(), String> {
+        request(
+            &self.commands,
+            |reply| HotkeyCommand::Unregister(id, reply),
+            || self.post(WM_HOTKEY_COMMAND),
+        )
+    }
+
     pub fn stop(mut self) {
+        self.post(WM_QUIT);
+        if let Some(j) = self.hotkey_thread_handle.take() {
+            let _ = j.join();
+        }
+    }
+
+    fn post(&self, message: u32) {
         unsafe {
             let _ = PostThreadMessageW(
                 self.thread_id,
-                WM_QUIT,
+                message,
                 Default::default(),
                 Default::default(),
             );
         }
-        if let Some(j) = self.hotkey_thread_handle.take() {
-            let _ = j.join();
-        }
     }
 }
 
-pub fn start_hotkey_listener() -> (HotkeyHandle, Receiver<KeyboardShortcut>) {
+// Returns once the activation chord is registered, or why it could not be.
+// The activation is reported with ACTIVATION_ID
+pub fn start_hotkey_listener(
+    activation: KeyboardShortcut,
+) -> Result<(HotkeyHandle, Receiver<HotkeyEvent>), String> {
     let (hk_event_tx, hk_event_rx) = mpsc::channel();
-    let (id_tx, id_rx) = mpsc::channel();
+    let (commands_tx, commands_rx) = mpsc::channel();
+    let (ready_tx, ready_rx) = mpsc::channel();
 
     let hotkey_thread_handle = thread::spawn(move || {
-        unsafe {
-            let _ = id_tx.send(GetCurrentThreadId());
-        }
-        if let Err(e) = hotkey_thread_main(hk_event_tx) {
-            eprintln!("hotkey thread error: {e:?}");
-        }
+        hotkey_thread_main(activation, ready_tx, commands_rx, hk_event_tx);
     });
 
-    let thread_id = id_rx.recv().expect("hotkey thread died early");
-    (
+    let thread_id = match ready_rx.recv() {
+        Ok(Ok(thread_id)) => thread_id,
+        Ok(Err(e)) => {
+            let _ = hotkey_thread_handle.join();
+            return Err(e);
+        }
+        Err(_) => return Err("hotkey thread died early".into()),
+    };
+    Ok((
         HotkeyHandle {
             thread_id,
+            commands: commands_tx,
             hotkey_thread_handle: Some(hotkey_thread_handle),
         },
         hk_event_rx,
-    )
+    ))
+}
+
+// Hotkeys belong to the thread that registered them, so this only runs on the
+// listener thread. The table id doubles as the RegisterHotKey id
+fn register(table: &mut HotkeyTable, shortcut: KeyboardShortcut) -> Result<HotkeyId, String> {
+    let vk = map_key(shortcut.key)?;
+    let id = table.insert(shortcut)?;
+    let registered = unsafe {
+        RegisterHotKey(
+            None,
+            id as i32,
+            map_modifier(&shortcut.modifier) | MOD_NOREPEAT,
+            vk.0 as u32,
+        )
+    };
+    if let Err(e) = registered {
+        let _ = table.remove(id);
+        return Err(format!(
+            "Could not register {shortcut}, another program may already use it ({e})"
+        ));
+    }
+    Ok(id)
+}
+
+fn unregister(table: &mut HotkeyTable, id: HotkeyId) -> Result<(), String> {
+    table.remove(id)?;
+    unsafe { UnregisterHotKey(None, id as i32) }
+        .map_err(|e| format!("Could not unregister hotkey {id}: {e}"))
 }
 
-fn hotkey_thread_main(tx: Sender<KeyboardShortcut>) -> Result<()> {
+fn hotkey_thread_main(
+    activation: KeyboardShortcut,
+    ready: Sender<Result<u32, String>>,
+    commands: Receiver<HotkeyCommand>,
+    tx: Sender<HotkeyEvent>,
+) {
+    let mut table = HotkeyTable::default();
     unsafe {
         // Ensure this thread has a message queue before RegisterHotKey
         let mut msg = MSG::default();
@@ -83,36 +145,42 @@ fn hotkey_thread_main(tx: Sender<KeyboardShortcut>) -> Result<()> {
         // before RegisterHotKey is called, making the code reliable.
         let _ = PeekMessageW(&mut msg, None, 0, 0, PM_NOREMOVE);
 
-        let hotkey_id = 1;
-        // TODO: this needs to be moved out to have a mapping not in windows module
-        RegisterHotKey(
-            None,
-            hotkey_id,
-            MOD_CONTROL | MOD_SHIFT | MOD_NOREPEAT,
-            VK_P.0 as u32,
-        )?;
+        if let Err(e) = register(&mut table, activation) {
+            let _ = ready.send(Err(e));
+            return;
+        }
+        let _ = ready.send(Ok(GetCurrentThreadId()));
 
         let mut msg = MSG::default();
         while GetMessageW(&mut msg, None, 0, 0).into() {
+            if msg.message == WM_HOTKEY_COMMAND {
+                while let Ok(command) = commands.try_recv() {
+                    match command {
+                        HotkeyCommand::Register(shortcut, reply) => {
+                            let _ = reply.send(register(&mut table, shortcut));
+                        }
+                        HotkeyCommand::Unregister(id, reply) => {
+                            let _ = reply.send(unregister(&mut table, id));
+                        }
+                    }
+                }
+            }
             if msg.message == WM_HOTKEY {
-                let id = msg.wParam.0 as i32;
+                let id = msg.wParam.0 as HotkeyId;
                 let lp = msg.lParam.0 as u32;
                 let modifiers = lp & 0xFFFF;
                 let vk: VIRTUAL_KEY = VIRTUAL_KEY(((lp >> 16) & 0xFFFF) as u16);
-                let shortcut = map_key_back(vk).map(|k| KeyboardShortcut {
-                    key: k,
+                // An unnamed key comes back as Key::Raw, the id is what matters
+                let shortcut = KeyboardShortcut {
+                    key: map_key_back(vk),
                     modifier: map_modifier_back(HOT_KEY_MODIFIERS(modifiers)),
-                });
-                if shortcut.is_none() {
-                    warn!("Mapping Fail {vk:?}");
-                    break;
-                }
-                let shortcut = shortcut.unwrap();
-                let _ = tx.send(shortcut);
+                };
+                let _ = tx.send(HotkeyEvent { id, shortcut });
             }
         }
+    }
 
-        let _ = UnregisterHotKey(None, hotkey_id);
-        Ok(())
+    for id in table.ids() {
+        let _ = unregister(&mut table, id);
     }
 }
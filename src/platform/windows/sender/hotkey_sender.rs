@@ -1,14 +1,53 @@
+use windows::Win32::Foundation::GetLastError;
 use windows::Win32::UI::Input::KeyboardAndMouse::{
-    SendInput, INPUT, INPUT_0, INPUT_TYPE, KEYBDINPUT, KEYBD_EVENT_FLAGS, KEYEVENTF_KEYUP,
-    KEYEVENTF_SCANCODE, VIRTUAL_KEY, VK_C, VK_CONTROL, VK_MENU, VK_TAB, VK_V,
+    GetAsyncKeyState, SendInput, INPUT, INPUT_0, INPUT_TYPE, KEYBDINPUT, KEYBD_EVENT_FLAGS,
+    KEYEVENTF_EXTENDEDKEY, KEYEVENTF_KEYUP, KEYEVENTF_SCANCODE, VIRTUAL_KEY, VK_APPS, VK_CONTROL,
+    VK_DELETE, VK_DIVIDE, VK_DOWN, VK_END, VK_HOME, VK_INSERT, VK_LEFT, VK_LWIN, VK_MENU, VK_NEXT,
+    VK_NUMLOCK, VK_PRIOR, VK_RCONTROL, VK_RIGHT, VK_RMENU, VK_RWIN, VK_SHIFT, VK_UP,
 };
 
+use crate::{
+    models::hotkey::HotkeyModifiers,
+    platform::{
+        key_sender::{KeyCode, KeyEvent, KeySender},
+        windows::mapper::hotkey_mapper::{map_key, map_modifier_key},
+    },
+};
+
+// Keys on the extended part of the keyboard. Without the flag Windows treats
+// them as their numpad twins
+fn is_extended_key(vk: VIRTUAL_KEY) -> bool {
+    matches!(
+        vk,
+        VK_INSERT
+            | VK_DELETE
+            | VK_HOME
+            | VK_END
+            | VK_PRIOR
+            | VK_NEXT
+            | VK_LEFT
+            | VK_RIGHT
+            | VK_UP
+            | VK_DOWN
+            | VK_LWIN
+            | VK_RWIN
+            | VK_RCONTROL
+            | VK_RMENU
+            | VK_DIVIDE
+            | VK_NUMLOCK
+            | VK_APPS
+    )
+}
+
 // Helper function to create a keyboard press/release event
 fn make_key_event(vk: VIRTUAL_KEY, is_release: bool) -> INPUT {
     let mut flags: KEYBD_EVENT_FLAGS = KEYBD_EVENT_FLAGS(0 as u32);
     if is_release {
         flags |= KEYEVENTF_KEYUP;
     }
+    if is_extended_key(vk) {
+        flags |= KEYEVENTF_EXTENDEDKEY;
+    }
 
     // The INPUT structure must be initialized carefully
     let mut input = INPUT {
@@ -26,29 +65,56 @@ fn make_key_event(vk: VIRTUAL_KEY, is_release: bool) -> INPUT {
     input
 }
 
-pub fn send_ctrl_v() {
-    // 1. CTRL Press
-    let ctrl_press = make_key_event(VK_MENU, false);
-    // 2. C Press
-    let c_press = make_key_event(VK_TAB, false);
-    // 3. C Release
-    let c_release = make_key_event(VK_TAB, true);
-    // 4. CTRL Release
-    let ctrl_release = make_key_event(VK_MENU, true);
+// SendInput inserts the whole batch atomically, no user input can interleave
+fn send_inputs(inputs: &[INPUT]) -> Result<(), String> {
+    let sent = unsafe { SendInput(inputs, std::mem::size_of::<INPUT>() as i32) };
+    if sent as usize != inputs.len() {
+        return Err(format!(
+            "SendInput injected {sent} of {} events: {:?}",
+            inputs.len(),
+            unsafe { GetLastError() }
+        ));
+    }
+    Ok(())
+}
 
-    let inputs = [ctrl_press, c_press, c_release, ctrl_release];
+pub struct WindowsKeySender;
 
-    unsafe {
-        // Send the sequence of four events
-        let result = SendInput(&inputs, std::mem::size_of::<INPUT>() as i32);
-        // Check result for success/failure...
+impl KeySender for WindowsKeySender {
+    fn send(&mut self, events: &[KeyEvent]) -> Result<(), String> {
+        let inputs: Vec<INPUT> = events
+            .iter()
+            .map(|event| {
+                let (code, is_release) = match event {
+                    KeyEvent::Down(code) => (code, false),
+                    KeyEvent::Up(code) => (code, true),
+                };
+                let vk = match code {
+                    KeyCode::Modifier(modifier) => map_modifier_key(*modifier),
+                    KeyCode::Key(key) => map_key(*key)?,
+                };
+                Ok(make_key_event(vk, is_release))
+            })
+            .collect::<Result<_, String>>()?;
+        send_inputs(&inputs)
+    }
+
+    fn held_modifiers(&self) -> HotkeyModifiers {
+        // The most significant bit is set while the key is down
+        let is_down = |vk: VIRTUAL_KEY| unsafe { GetAsyncKeyState(vk.0 as i32) } < 0;
+        HotkeyModifiers {
+            control: is_down(VK_CONTROL),
+            shift: is_down(VK_SHIFT),
+            alt: is_down(VK_MENU),
+            win: is_down(VK_LWIN) || is_down(VK_RWIN),
+        }
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use windows::Win32::Foundation::GetLastError;
+    use windows::Win32::UI::Input::KeyboardAndMouse::VK_TAB;
 
     #[test]
     fn test_send_alt_tab_sequence_correctness() {
@@ -68,7 +134,6 @@ mod tests {
         }
     }
 
-    use windows::Win32::UI::Input::KeyboardAndMouse::VK_DELETE;
     #[test]
     fn test_send_ctrl_alt_del_sequence_correctness() {
         // Arrange: Prepare the key sequence
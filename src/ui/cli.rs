@@ -0,0 +1,373 @@
+// Command line mode, to check extension files without starting the palette.
+// `global_palette <command>` prints its result and exits, see USAGE
+
+use std::path::{Path, PathBuf};
+
+use crate::{
+    core::{
+        app_config::load_app_config,
+        extensions::sources::ExtensionSources,
+        registry::{conflicts, registry::MasterRegistry},
+        search::get_score,
+    },
+    models::action::{ActionKind, Os},
+};
+
+const USAGE: &str = "Usage:
+  global_palette validate [--os <os>] <files>...  Check extension files, non-zero exit on problems
+  global_palette list [--os <os>]                 Actions of every configured extension, non-zero exit if one does not load
+  global_palette explain [--os <os>] <app> <action>
+                                                  Effective binding of one action
+  global_palette conflicts                        Shortcut conflicts, non-zero exit if any
+<os> is windows, macos or linux, the current OS by default";
+
+#[derive(Debug, PartialEq)]
+enum Command {
+    Validate(Vec<PathBuf>),
+    List,
+    Explain(String, String),
+    Conflicts,
+}
+
+// None when `args` do not name a command, the palette should start then.
+// Only the commands over the configured extensions read `config_path`
+pub fn run(args: &[String], config_path: &Path, current_os: Os) -> Option<i32> {
+    args.first()?;
+    let (command, os) = match parse(args, current_os) {
+        Ok(parsed) => parsed,
+        Err(e) => {
+            eprintln!("{e}\n\n{USAGE}");
+            return Some(2);
+        }
+    };
+
+    let code = match command {
+        Command::Validate(files) => validate(&MasterRegistry::from_files(&files, os)),
+        Command::List => with_configured(config_path, os, list),
+        Command::Explain(app, action) => {
+            with_configured(config_path, os, |registry| explain(registry, &app, &action))
+        }
+        Command::Conflicts => with_configured(config_path, os, show_conflicts),
+    };
+    Some(code)
+}
+
+fn with_configured(
+    config_path: &Path,
+    os: Os,
+    command: impl FnOnce(&MasterRegistry) -> i32,
+) -> i32 {
+    match load_app_config(config_path) {
+        Ok(app_config) => command(&MasterRegistry::from_sources(
+            &ExtensionSources::new(&app_config).resolve(),
+            os,
+        )),
+        Err(e) => {
+            eprintln!("{e}");
+            1
+        }
+    }
+}
+
+fn parse(args: &[String], current_os: Os) -> Result<(Command, Os), String> {
+    let mut os = current_os;
+    let mut positional = vec![];
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        if arg == "--os" {
+            let value = args.next().ok_or("--os needs a value")?;
+            os = Os::from_config_key(value).ok_or_else(|| format!("Unknown OS `{value}`"))?;
+        } else {
+            positional.push(arg.as_str());
+        }
+    }
+
+    let command = match positional.as_slice() {
+        ["validate", files @ ..] if !files.is_empty() => {
+            Command::Validate(files.iter().map(PathBuf::from).collect())
+        }
+        ["validate"] => return Err("validate needs at least one file".into()),
+        ["list"] => Command::List,
+        ["explain", app, action] => Command::Explain(app.to_string(), action.to_string()),
+        ["conflicts"] => Command::Conflicts,
+        _ => return Err(format!("Unknown command `{}`", positional.join(" "))),
+    };
+    Ok((command, os))
+}
+
+// Schema problems of every file, then shortcut conflicts between them.
+// Warnings, ie deprecations or actions missing for the OS, do not fail the check
+fn validate(registry: &MasterRegistry) -> i32 {
+    for warning in registry.load_warnings() {
+        println!("warning: {warning}");
+    }
+    let errors = registry.load_errors();
+    for err in &errors {
+        println!("{err}");
+    }
+
+    let report = conflicts::analyse(registry);
+    if !report.is_empty() {
+        print!("{report}");
+    }
+
+    if errors.is_empty() && report.is_empty() {
+        println!("No problems found");
+        0
+    } else {
+        1
+    }
+}
+
+fn list(registry: &MasterRegistry) -> i32 {
+    let mut app_ids: Vec<_> = registry.application_registry.keys().collect();
+    app_ids.sort();
+
+    for app_id in app_ids {
+        let app = &registry.application_registry[app_id];
+        match app.application_process_name() {
+            Some(process) => println!("{app_id} ({}, process {process})", app.application_name()),
+            None => println!("{app_id} ({}, no process)", app.application_name()),
+        }
+
+        let mut actions: Vec<_> = app.actions().iter().collect();
+        actions.sort_by_key(|(action_id, _)| *action_id);
+        for (action_id, action) in actions {
+            println!(
+                "  {:<20} {:<24} {:<16} {:<10} {:?}",
+                action_id,
+                action.name,
+                action.kind.to_string(),
+                format!("{:?}", action.focus_state),
+                action.priority
+            );
+        }
+    }
+
+    let errors = registry.load_errors();
+    for err in &errors {
+        eprintln!("{err}");
+    }
+    if errors.is_empty() {
+        0
+    } else {
+        1
+    }
+}
+
+fn show_conflicts(registry: &MasterRegistry) -> i32 {
+    let report = conflicts::analyse(registry);
+    print!("{report}");
+    if report.is_empty() {
+        0
+    } else {
+        1
+    }
+}
+
+fn explain(registry: &MasterRegistry, app: &str, action: &str) -> i32 {
+    let id = format!("{app}/{action}");
+    let Some(found) = registry.find_action(&id) else {
+        eprintln!("No action {id} for this OS");
+        if let Some(closest) = closest_action(registry, &id) {
+            eprintln!("Did you mean {closest}?");
+        }
+        return 1;
+    };
+
+    println!("{id}: {}", found.action_name);
+    if let Some(source) = registry.source_of(app) {
+        println!("  from:        {}", source.display());
+    }
+    println!("  binding:     {}", found.kind);
+    match &found.kind {
+        ActionKind::Macro(steps) => {
+            for step in steps {
+                println!("    {step}");
+            }
+        }
+        ActionKind::TypeText(snippet) => {
+            println!(
+                "    {:?}, delivered as {:?}",
+                snippet.text, snippet.delivery
+            )
+        }
+        _ => {}
+    }
+    println!(
+        "  priority:    {:?} ({})",
+        found.priority,
+        found.priority.layer()
+    );
+    println!("  focus state: {:?}", found.focus_state);
+
+    let report = conflicts::analyse(registry);
+    let is_this = |b: &conflicts::BindingRef| b.app_id == app && b.action_id == action;
+    for conflict in &report.conflicts {
+        if is_this(&conflict.winner) {
+            for shadowed in &conflict.shadowed {
+                println!("  shadows:     {shadowed}");
+            }
+        } else if conflict.shadowed.iter().any(is_this) {
+            println!("  shadowed by: {}", conflict.winner);
+        }
+    }
+    if report.is_shadowed(app, action) {
+        println!("  never fires, a higher ranked action takes its keys");
+    }
+    0
+}
+
+// Best fuzzy match of a mistyped `app/action` id
+fn closest_action(registry: &MasterRegistry, id: &str) -> Option<String> {
+    registry
+        .application_registry
+        .iter()
+        .flat_map(|(app_id, app)| {
+            app.actions()
+                .keys()
+                .map(move |action_id| format!("{app_id}/{action_id}"))
+        })
+        .map(|candidate| (get_score(&candidate, id).score, candidate))
+        .filter(|(score, _)| *score > 0)
+        .max_by(|(a_score, a), (b_score, b)| a_score.cmp(b_score).then(b.cmp(a)))
+        .map(|(_, candidate)| candidate)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(line: &str) -> Vec<String> {
+        line.split_whitespace().map(String::from).collect()
+    }
+
+    #[test]
+    fn parses_commands_and_os() {
+        assert_eq!(
+            parse(&args("validate a.toml b.toml"), Os::Windows),
+            Ok((
+                Command::Validate(vec!["a.toml".into(), "b.toml".into()]),
+                Os::Windows
+            ))
+        );
+        assert_eq!(
+            parse(&args("list --os linux"), Os::Windows),
+            Ok((Command::List, Os::Linux))
+        );
+        assert_eq!(
+            parse(&args("explain --os macos chrome new_tab"), Os::Windows),
+            Ok((Command::Explain("chrome".into(), "new_tab".into()), Os::Mac))
+        );
+    }
+
+    const CHROME: &str = r#"version = 2
+
+[app]
+id = "chrome"
+name = "Chrome"
+default_focus_state = "focused"
+default_priority = "application"
+
+[app.application_os_name]
+windows = "chrome.exe"
+
+[actions.new_tab]
+name = "New tab"
+cmd.windows = { mods = ["ctrl"], key = "t" }
+"#;
+
+    // Exit code of `validate` over the files, written to a temp dir first.
+    // The config.toml there is broken, validate does not read it
+    fn validate_files(files: &[(&str, &str)]) -> Option<i32> {
+        let dir = tempfile::tempdir().unwrap();
+        let config_path = dir.path().join("config.toml");
+        std::fs::write(&config_path, "activation = ").unwrap();
+        let mut args = vec!["validate".to_string()];
+        for (name, content) in files {
+            let path = dir.path().join(name);
+            std::fs::write(&path, content).unwrap();
+            args.push(path.display().to_string());
+        }
+        run(&args, &config_path, Os::Windows)
+    }
+
+    #[test]
+    fn validate_passes_clean_files() {
+        assert_eq!(validate_files(&[("chrome.toml", CHROME)]), Some(0));
+
+        // Deprecated, but still loads
+        let v1 = CHROME
+            .replace("version = 2", "version = 1")
+            .replace("\"application\"", "\"Application\"");
+        assert_eq!(validate_files(&[("chrome.toml", &v1)]), Some(0));
+
+        // Nothing for linux is a warning
+        let linux_only = CHROME.replace(".windows", ".linux");
+        assert_eq!(validate_files(&[("chrome.toml", &linux_only)]), Some(0));
+    }
+
+    #[test]
+    fn validate_fails_on_broken_files_and_conflicts() {
+        assert_eq!(validate_files(&[("chrome.toml", "version = ")]), Some(1));
+
+        let no_focus_state = CHROME.replace("default_focus_state = \"focused\"\n", "");
+        assert_eq!(validate_files(&[("chrome.toml", &no_focus_state)]), Some(1));
+
+        let conflict = format!(
+            "{CHROME}
+[actions.vimium_new_tab]
+name = \"Vimium new tab\"
+cmd.windows = {{ mods = [\"ctrl\"], key = \"t\" }}
+"
+        );
+        assert_eq!(validate_files(&[("chrome.toml", &conflict)]), Some(1));
+
+        let other = CHROME.replace("New tab", "Other tab");
+        assert_eq!(
+            validate_files(&[("chrome.toml", CHROME), ("other.toml", &other)]),
+            Some(1)
+        );
+    }
+
+    // Exit code of `list` over the files of one extension folder
+    fn list_files(files: &[(&str, &str)]) -> Option<i32> {
+        let dir = tempfile::tempdir().unwrap();
+        let extensions = dir.path().join("extensions");
+        std::fs::create_dir(&extensions).unwrap();
+        for (name, content) in files {
+            std::fs::write(extensions.join(name), content).unwrap();
+        }
+        let config_path = dir.path().join("config.toml");
+        let config = format!("search_dirs = ['{}']", extensions.display());
+        std::fs::write(&config_path, config).unwrap();
+        run(&args("list"), &config_path, Os::Windows)
+    }
+
+    #[test]
+    fn list_fails_on_files_that_do_not_load() {
+        assert_eq!(list_files(&[("chrome.toml", CHROME)]), Some(0));
+        assert_eq!(
+            list_files(&[("chrome.toml", CHROME), ("broken.toml", "version = ")]),
+            Some(1)
+        );
+    }
+
+    #[test]
+    fn configured_commands_fail_on_a_broken_config() {
+        let dir = tempfile::tempdir().unwrap();
+        let config_path = dir.path().join("config.toml");
+        std::fs::write(&config_path, "activation = ").unwrap();
+        assert_eq!(run(&args("list"), &config_path, Os::Windows), Some(1));
+        assert_eq!(run(&args("conflicts"), &config_path, Os::Windows), Some(1));
+    }
+
+    #[test]
+    fn rejects_bad_arguments() {
+        assert!(parse(&args("validate"), Os::Linux).is_err());
+        assert!(parse(&args("list --os beos"), Os::Linux).is_err());
+        assert!(parse(&args("list --os"), Os::Linux).is_err());
+        assert!(parse(&args("explain chrome"), Os::Linux).is_err());
+        assert!(parse(&args("frobnicate"), Os::Linux).is_err());
+    }
+}
@@ -1,2 +1,2 @@
-pub mod combo_box;
+pub mod cli;
 pub mod ui_main;
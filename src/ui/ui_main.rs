@@ -1,21 +1,36 @@
 use eframe::egui;
-use std::fmt;
-use std::sync::mpsc::{self, Receiver};
-
-/// Represents a single command entry shown in the palette UI
-pub struct Command {
-    pub label: String,
-    // Placeholder for the command action; wiring happens elsewhere.
-    pub action: Box<dyn Fn() + Send + Sync>,
-}
+use log::debug;
+use std::path::PathBuf;
+use std::sync::mpsc::Receiver;
+use std::time::{Duration, Instant};
 
-impl fmt::Debug for Command {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        f.debug_struct("Command")
-            .field("label", &self.label)
-            .field("action", &"<function>")
-            .finish()
-    }
+use crate::{
+    core::{
+        history::UsageHistory,
+        registry::registry::UnitAction,
+        search::{rank, PreparedQuery},
+    },
+    models::app_config::UiConfig,
+    platform::register_receiver::HotkeyId,
+};
+
+// Time for the hide viewport commands to take effect before an action runs
+const RUN_DELAY: Duration = Duration::from_millis(100);
+
+/// Supplies the palette with actions and runs the chosen one. Wired up in main.
+pub trait PaletteBackend {
+    /// Called every time the palette opens, before it takes focus.
+    fn open(&mut self) -> Vec<UnitAction>;
+    /// Usage history used to rank the results.
+    fn history(&self) -> &UsageHistory;
+    /// Called once the palette is hidden, so focus can go back to the target window.
+    fn run(&mut self, action: &UnitAction);
+    /// A direct hotkey was pressed, runs its action on the focused window.
+    fn run_hotkey(&mut self, id: HotkeyId);
+    /// Extension files changed on disk, rebuilds the apps they define.
+    fn reload_extensions(&mut self, paths: &[PathBuf]);
+    /// Extension files that failed to load, shown above the results.
+    fn extension_errors(&self) -> Vec<String>;
 }
 
 /// Core UI state for the command palette.
@@ -23,9 +38,11 @@ impl fmt::Debug for Command {
 pub struct CommandPaletteApp {
     /// What the user is currently typing.
     pub filter_text: String,
-    /// All possible commands available in the system.
-    pub all_commands: Vec<Command>,
-    /// Indices of commands from `all_commands` that match `filter_text`.
+    /// All actions available in the context the palette was opened in.
+    pub all_actions: Vec<UnitAction>,
+    /// `filter_text` prepared for scoring, redone on every keystroke.
+    pub query: PreparedQuery,
+    /// Indices of actions from `all_actions` that match `filter_text`, best first.
     pub filtered_indices: Vec<usize>,
     /// Which item in the filtered list is currently highlighted (via arrow keys).
     pub selected_index: usize,
@@ -34,21 +51,32 @@ pub struct CommandPaletteApp {
 }
 
 impl CommandPaletteApp {
-    fn new(all_commands: Vec<Command>) -> Self {
-        let mut s = Self {
+    fn new(all_actions: Vec<UnitAction>) -> Self {
+        Self {
             filter_text: String::new(),
-            all_commands,
+            query: PreparedQuery::default(),
+            all_actions,
             filtered_indices: Vec::new(),
             selected_index: 0,
             is_open: true, // Start visible
-        };
-        s
+        }
+    }
+
+    fn refilter(&mut self, history: &UsageHistory) {
+        self.query = PreparedQuery::new(&self.filter_text);
+        self.filtered_indices = rank(&self.query, &self.all_actions, history)
+            .into_iter()
+            .map(|(idx, _)| idx)
+            .collect();
+        self.selected_index = 0;
     }
 }
 
 #[derive(Debug)]
 pub enum UiSignal {
     ToggleVisibility,
+    Hotkey(HotkeyId), // A direct hotkey, the palette stays as it is
+    ExtensionsChanged(Vec<PathBuf>),
 }
 
 struct App {
@@ -57,21 +85,49 @@ struct App {
 
     // State
     palette: CommandPaletteApp,
+    backend: Box<dyn PaletteBackend>,
+    pending_run: Option<(usize, Instant)>, // Selected action waiting for the palette to hide
+    extension_errors: Vec<String>,
+    config: UiConfig,
 }
 
 impl App {
-    fn new(_cc: &eframe::CreationContext<'_>, receiver: Receiver<UiSignal>) -> Self {
-        // Example commands (replace with real actions when wiring platform)
-        let demo_commands = vec![Command {
-            label: "Quit".to_string(),
-            action: Box::new(|| println!("Action: Quit")),
-        }];
+    fn new(
+        _cc: &eframe::CreationContext<'_>,
+        receiver: Receiver<UiSignal>,
+        mut backend: Box<dyn PaletteBackend>,
+        config: UiConfig,
+    ) -> Self {
+        let mut palette = CommandPaletteApp::new(backend.open());
+        palette.refilter(backend.history());
+        let extension_errors = backend.extension_errors();
 
         Self {
-            palette: CommandPaletteApp::new(demo_commands),
+            palette,
             receiver,
+            backend,
+            pending_run: None,
+            extension_errors,
+            config,
         }
     }
+
+    fn hide(&mut self, ctx: &egui::Context) {
+        self.palette.is_open = false;
+        // Hide window by making it minimized and off-screen
+        // This keeps it hidden from Alt+Tab but allows update loop to continue
+        // this is very hacky
+        ctx.send_viewport_cmd(egui::ViewportCommand::Minimized(true));
+        ctx.send_viewport_cmd(egui::ViewportCommand::OuterPosition(egui::pos2(
+            -2000.0, -2000.0,
+        )));
+    }
+
+    // Hide first, the action runs on a later frame once focus can leave the palette
+    fn select(&mut self, ctx: &egui::Context, orig_idx: usize) {
+        self.hide(ctx);
+        self.pending_run = Some((orig_idx, Instant::now()));
+    }
 }
 
 impl eframe::App for App {
@@ -80,10 +136,8 @@ impl eframe::App for App {
         ctx.request_repaint();
 
         // CRITICAL: Always process incoming UI signals regardless of visibility
-        let mut signal_received = false;
         while let Ok(sig) = self.receiver.try_recv() {
-            signal_received = true;
-            dbg!(sig);
+            debug!("{sig:?}");
             match sig {
                 UiSignal::ToggleVisibility => {
                     let old_state = self.palette.is_open;
@@ -94,24 +148,36 @@ impl eframe::App for App {
                     );
 
                     if self.palette.is_open {
+                        // Capture the context before the palette steals focus
+                        self.palette.all_actions = self.backend.open();
+                        self.palette.filter_text.clear();
+                        self.palette.refilter(self.backend.history());
+
                         // Show and focus the window
                         ctx.send_viewport_cmd(egui::ViewportCommand::Minimized(false));
                         ctx.send_viewport_cmd(egui::ViewportCommand::Visible(true));
                         ctx.send_viewport_cmd(egui::ViewportCommand::Focus);
-                        self.palette.filter_text.clear();
-                        self.palette.selected_index = 0;
                         println!("Window should now be visible and focused");
                     } else {
-                        // Hide window by making it minimized and off-screen
-                        // This keeps it hidden from Alt+Tab but allows update loop to continue
-                        // this is very hacky
-                        ctx.send_viewport_cmd(egui::ViewportCommand::Minimized(true));
-                        ctx.send_viewport_cmd(egui::ViewportCommand::OuterPosition(egui::pos2(
-                            -2000.0, -2000.0,
-                        )));
+                        self.hide(ctx);
                         println!("Window minimized and moved off-screen (hidden from Alt+Tab)");
                     }
                 }
+                UiSignal::Hotkey(id) => self.backend.run_hotkey(id),
+                // Picked up the next time the palette opens
+                UiSignal::ExtensionsChanged(paths) => {
+                    self.backend.reload_extensions(&paths);
+                    self.extension_errors = self.backend.extension_errors();
+                }
+            }
+        }
+
+        if let Some((orig_idx, selected_at)) = self.pending_run {
+            if selected_at.elapsed() >= RUN_DELAY {
+                self.pending_run = None;
+                if let Some(action) = self.palette.all_actions.get(orig_idx) {
+                    self.backend.run(action);
+                }
             }
         }
 
@@ -121,7 +187,7 @@ impl eframe::App for App {
             ctx.send_viewport_cmd(egui::ViewportCommand::OuterPosition(egui::pos2(
                 (ctx.input(|i| i.viewport().monitor_size.map(|m| m.x))
                     .unwrap_or(1920.0)
-                    - 600.0)
+                    - self.config.width)
                     / 2.0,
                 ctx.input(|i| i.viewport().monitor_size.map(|m| m.y))
                     .unwrap_or(1080.0)
@@ -130,29 +196,28 @@ impl eframe::App for App {
 
             // Hide on Escape (same behavior as Ctrl+Shift+P)
             if ctx.input(|i| i.key_pressed(egui::Key::Escape)) {
-                self.palette.is_open = false;
-                // Hide window by making it minimized and off-screen
-                ctx.send_viewport_cmd(egui::ViewportCommand::Minimized(true));
-                ctx.send_viewport_cmd(egui::ViewportCommand::OuterPosition(egui::pos2(
-                    -2000.0, -2000.0,
-                )));
+                self.hide(ctx);
                 println!(
                     "Escape pressed: Window minimized and moved off-screen (hidden from Alt+Tab)"
                 );
             }
 
             // Keyboard navigation (only when visible)
-            let visible_count = self.palette.filtered_indices.len().min(8);
+            let visible_count = self
+                .palette
+                .filtered_indices
+                .len()
+                .min(self.config.max_results);
             if visible_count > 0 {
-                if ctx.input(|i| i.key_pressed(egui::Key::ArrowDown)) {
-                    if self.palette.selected_index + 1 < visible_count {
-                        self.palette.selected_index += 1;
-                    }
+                if ctx.input(|i| i.key_pressed(egui::Key::ArrowDown))
+                    && self.palette.selected_index + 1 < visible_count
+                {
+                    self.palette.selected_index += 1;
                 }
-                if ctx.input(|i| i.key_pressed(egui::Key::ArrowUp)) {
-                    if self.palette.selected_index > 0 {
-                        self.palette.selected_index -= 1;
-                    }
+                if ctx.input(|i| i.key_pressed(egui::Key::ArrowUp))
+                    && self.palette.selected_index > 0
+                {
+                    self.palette.selected_index -= 1;
                 }
                 if ctx.input(|i| i.key_pressed(egui::Key::Enter)) {
                     if let Some(&orig_idx) = self
@@ -160,20 +225,18 @@ impl eframe::App for App {
                         .filtered_indices
                         .get(self.palette.selected_index)
                     {
-                        (self.palette.all_commands[orig_idx].action)();
-                        self.palette.is_open = false;
-                        ctx.send_viewport_cmd(egui::ViewportCommand::Minimized(true));
-                        ctx.send_viewport_cmd(egui::ViewportCommand::OuterPosition(egui::pos2(
-                            -2000.0, -2000.0,
-                        )));
+                        self.select(ctx, orig_idx);
                     }
                 }
             }
 
             // Dynamically adjust viewport height based on results
-            let desired_height = 44.0 + (visible_count as f32) * 28.0 + 12.0; // input + rows + padding
+            let desired_height = 44.0
+                + (self.extension_errors.len() as f32) * 20.0
+                + (visible_count as f32) * 28.0
+                + 12.0; // input + errors + rows + padding
             ctx.send_viewport_cmd(egui::ViewportCommand::InnerSize(egui::vec2(
-                600.0,
+                self.config.width,
                 desired_height.max(80.0),
             )));
         }
@@ -181,10 +244,10 @@ impl eframe::App for App {
         // The UI (render only when visible)
         if self.palette.is_open {
             egui::CentralPanel::default().show(ctx, |ui| {
-                let frame = egui::Frame::none()
+                let frame = egui::Frame::NONE
                     .fill(egui::Color32::from_rgba_unmultiplied(30, 30, 30, 230))
                     .stroke(egui::Stroke::new(1.0, egui::Color32::from_gray(60)))
-                    .rounding(6.0);
+                    .corner_radius(6.0);
 
                 frame.show(ui, |ui| {
                     ui.set_min_width(ui.available_width());
@@ -197,30 +260,50 @@ impl eframe::App for App {
                     );
                     resp.request_focus();
                     if resp.changed() {
-                        self.palette.selected_index = 0;
+                        self.palette.refilter(self.backend.history());
                     }
 
                     ui.add_space(6.0);
 
+                    // Broken extension files, their last good version is still in use
+                    for error in &self.extension_errors {
+                        ui.label(egui::RichText::new(error).color(egui::Color32::LIGHT_RED));
+                    }
+
                     // Results list (limited)
-                    for (idx, &orig_idx) in self.palette.filtered_indices.iter().take(8).enumerate()
+                    let mut clicked = None;
+                    for (idx, &orig_idx) in self
+                        .palette
+                        .filtered_indices
+                        .iter()
+                        .take(self.config.max_results)
+                        .enumerate()
                     {
                         let is_selected = idx == self.palette.selected_index;
-                        let label = &self.palette.all_commands[orig_idx].label;
+                        let action = &self.palette.all_actions[orig_idx];
+                        // "Category: Name" like the VS Code palette
+                        let name = match &action.category {
+                            Some(category) => format!("{category}: {}", action.action_name),
+                            None => action.action_name.clone(),
+                        };
+                        let label = format!("{name}  ·  {}    {}", action.app_name, action.kind);
                         let row = ui.selectable_label(is_selected, label);
                         if row.clicked() {
-                            self.palette.selected_index = idx;
-                            (self.palette.all_commands[orig_idx].action)();
-                            self.palette.is_open = false;
-                            ctx.send_viewport_cmd(egui::ViewportCommand::Minimized(true));
-                            ctx.send_viewport_cmd(egui::ViewportCommand::OuterPosition(
-                                egui::pos2(-2000.0, -2000.0),
-                            ));
+                            clicked = Some((idx, orig_idx));
                         }
                     }
+                    if let Some((idx, orig_idx)) = clicked {
+                        self.palette.selected_index = idx;
+                        self.select(ctx, orig_idx);
+                    }
                     if self.palette.filtered_indices.is_empty() {
+                        let message = if self.palette.query.is_empty() {
+                            "No commands".to_string()
+                        } else {
+                            format!("No commands match \"{}\"", self.palette.query.original)
+                        };
                         ui.label(
-                            egui::RichText::new("No commands")
+                            egui::RichText::new(message)
                                 .italics()
                                 .color(egui::Color32::GRAY),
                         );
@@ -231,8 +314,8 @@ impl eframe::App for App {
     }
 }
 
-pub fn ui_main(receiver: Receiver<UiSignal>) {
-    let width = 600.0;
+pub fn ui_main(receiver: Receiver<UiSignal>, backend: Box<dyn PaletteBackend>, config: UiConfig) {
+    let width = config.width;
     let height = 180.0;
 
     let options = eframe::NativeOptions {
@@ -250,6 +333,6 @@ pub fn ui_main(receiver: Receiver<UiSignal>) {
     let _ = eframe::run_native(
         "Command Palette",
         options,
-        Box::new(move |_cc| Ok(Box::new(App::new(_cc, receiver)))),
+        Box::new(move |_cc| Ok(Box::new(App::new(_cc, receiver, backend, config)))),
     );
 }